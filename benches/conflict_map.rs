@@ -0,0 +1,137 @@
+//! Conflict index of a synthetic large load order
+//!
+//! Run with `cargo bench --bench conflict_map`
+//! The sharded index uses one thread per core, run it again with `RAYON_NUM_THREADS=1`
+//! to compare it with a single thread
+
+use std::{collections::HashMap, hint::black_box, path::PathBuf, time::Instant};
+
+use tes3edit::{CompareData, CompareItemViewModel};
+
+const PLUGINS: usize = 400;
+const RECORDS_PER_PLUGIN: usize = 5000;
+/// ids shared by all plugins, like the records of the masters
+const SHARED_RECORDS: usize = 50_000;
+const RUNS: usize = 5;
+
+/// A load order where every plugin overrides some shared records and adds its own
+fn create_load_order() -> CompareData {
+    let mut data = CompareData::default();
+    for p in 0..PLUGINS {
+        let records = (0..RECORDS_PER_PLUGIN)
+            .map(|r| {
+                // every plugin overrides a different window of the shared records
+                if r % 2 == 0 {
+                    format!("MISC,shared_{}", (p * 7919 + r) % SHARED_RECORDS)
+                } else {
+                    format!("MISC,plugin_{}_{}", p, r)
+                }
+            })
+            .collect();
+        data.plugins.push(CompareItemViewModel {
+            id: p as u64,
+            path: PathBuf::from(format!("plugin_{}.esp", p)),
+            enabled: true,
+            records,
+            ..Default::default()
+        });
+    }
+    data
+}
+
+/// The previous serial implementation that cloned the plugin list on every hit
+fn legacy_conflict_map(data: &CompareData) -> HashMap<String, Vec<u64>> {
+    let mut conflict_map: HashMap<String, Vec<u64>> = HashMap::default();
+    for plugin in data.plugins.iter().filter(|e| e.enabled) {
+        let mut new_records: Vec<String> = vec![];
+        for record_id in plugin.records.iter() {
+            if conflict_map.contains_key(record_id) {
+                let mut v = conflict_map[record_id].clone();
+                v.push(plugin.id);
+                conflict_map.insert(record_id.clone(), v);
+            } else {
+                new_records.push(record_id.clone());
+            }
+        }
+        for id in new_records {
+            conflict_map.insert(id, vec![plugin.id]);
+        }
+    }
+    conflict_map
+        .into_iter()
+        .filter(|(_, v)| v.len() > 1)
+        .collect()
+}
+
+/// Average time of a function over all runs in milliseconds
+fn measure<T>(name: &str, mut f: impl FnMut() -> T) -> f64 {
+    let mut total = 0.0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        black_box(f());
+        total += start.elapsed().as_secs_f64() * 1000.0;
+    }
+    let average = total / RUNS as f64;
+    println!("{:<24} {:>10.1} ms", name, average);
+    average
+}
+
+fn main() {
+    let data = create_load_order();
+    println!(
+        "{} plugins, {} records, threads={}",
+        PLUGINS,
+        PLUGINS * RECORDS_PER_PLUGIN,
+        rayon::current_num_threads()
+    );
+
+    let threads = rayon::current_num_threads();
+    let legacy = measure("legacy, serial", || legacy_conflict_map(&data));
+    let mut data = data;
+    let sharded = measure(&format!("sharded, threads={}", threads), || {
+        data.build_conflicts();
+    });
+    println!("{:<24} {:>10.1}x", "speedup vs legacy", legacy / sharded);
+    if threads == 1 {
+        println!("only one thread available, these are no multi-core numbers");
+    }
+
+    // the sharded index has the same conflicts as the legacy map
+    let map = legacy_conflict_map(&data);
+    assert_eq!(data.conflicts.len(), map.len());
+    for id in data.conflicts.get_conflicting_ids() {
+        assert_eq!(data.conflicts.get(id), map.get(id));
+    }
+
+    // and so has the index of one plugin after the other
+    let mut incremental = CompareData {
+        plugins: std::mem::take(&mut data.plugins),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    for i in 0..incremental.plugins.len() {
+        incremental.index_plugin(i);
+    }
+    println!(
+        "{:<24} {:>10.1} ms",
+        "one plugin at a time",
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    assert_eq!(incremental.conflicts.len(), map.len());
+    let mut data = incremental;
+
+    // toggling one plugin only touches its own records
+    let last = data.plugins.len() - 1;
+    let mut total = 0.0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        data.unindex_plugin(last);
+        data.index_plugin(last);
+        total += start.elapsed().as_secs_f64() * 1000.0;
+    }
+    println!(
+        "{:<24} {:>10.1} ms",
+        "toggle one plugin",
+        total / RUNS as f64
+    );
+}
@@ -2,10 +2,17 @@
 use std::{cell::RefCell, rc::Rc};
 use std::{collections::HashMap, path::PathBuf};
 
-use crate::{get_all_tags, RecordsData};
+use crate::{
+    dialogue::get_info_topics,
+    get_all_tags,
+    gmst::get_evil_gmsts,
+    loader::{ELoadStatus, PluginLoader},
+    DialogueViewData, HeightmapData, MapData, QueryData, QuestData, RecordsData, TableData,
+    TextSearchData,
+};
 use egui_notify::Toasts;
 use serde::{Deserialize, Serialize};
-use tes3::esp::{EditorId, Plugin, TypeInfo};
+use tes3::esp::Plugin;
 
 use crate::{get_unique_id, CompareData, EAppState, EModalState, EScale, EditData, PluginMetadata};
 
@@ -31,6 +38,20 @@ pub struct TemplateApp {
     pub compare_data: CompareData,
     #[serde(skip)]
     pub records_data: RecordsData,
+    #[serde(skip)]
+    pub map_data: MapData,
+    #[serde(skip)]
+    pub heightmap_data: HeightmapData,
+    #[serde(skip)]
+    pub dialogue_data: DialogueViewData,
+    #[serde(skip)]
+    pub quest_data: QuestData,
+    #[serde(skip)]
+    pub text_search_data: TextSearchData,
+    #[serde(skip)]
+    pub query_data: QueryData,
+    #[serde(skip)]
+    pub table_data: TableData,
 
     // runtime ui
     #[serde(skip)]
@@ -64,6 +85,13 @@ impl Default for TemplateApp {
             compare_data: CompareData::default(),
             edit_data: EditData::default(),
             records_data: RecordsData::default(),
+            map_data: MapData::default(),
+            heightmap_data: HeightmapData::default(),
+            dialogue_data: DialogueViewData::default(),
+            quest_data: QuestData::default(),
+            text_search_data: TextSearchData::default(),
+            query_data: QueryData::default(),
+            table_data: TableData::default(),
             // settings
             overwrite: false,
             use_experimental: false,
@@ -116,6 +144,11 @@ impl TemplateApp {
                 self.recent_plugins.remove(0);
             }
 
+            // vanilla GMST defaults from the masters next to the plugin
+            if let Some(folder) = path.parent() {
+                self.edit_data.gmst_defaults.load(folder);
+            }
+
             let plugin_id = path.to_str().unwrap().to_string();
             self.edit_data.current_plugin_id.clone_from(&plugin_id);
 
@@ -132,17 +165,39 @@ impl TemplateApp {
                 plugin_data.records.clear();
 
                 // add new data
+                plugin_data.topics = get_info_topics(&plugin.objects);
                 for record in plugin.objects {
                     plugin_data.records.insert(get_unique_id(&record), record);
                 }
+                self.warn_evil_gmsts();
             } else {
                 // insert new
                 let mut data = PluginMetadata::new(plugin_id, Some(path));
                 // add new data
+                data.topics = get_info_topics(&plugin.objects);
                 for record in plugin.objects {
                     data.records.insert(get_unique_id(&record), record);
                 }
                 self.edit_data.plugins.push(data);
+                self.warn_evil_gmsts();
+            }
+        }
+    }
+
+    /// Warns if the current plugin contains evil GMSTs
+    fn warn_evil_gmsts(&mut self) {
+        if let Some(data) = self
+            .edit_data
+            .plugins
+            .iter()
+            .find(|p| p.id == self.edit_data.current_plugin_id)
+        {
+            let evil_gmsts = get_evil_gmsts(data);
+            if !evil_gmsts.is_empty() {
+                self.toasts.warning(format!(
+                    "Plugin contains {} evil GMSTs, strip them from the GMST context menu",
+                    evil_gmsts.len()
+                ));
             }
         }
     }
@@ -190,36 +245,77 @@ impl TemplateApp {
             }
         }
 
+        // plugins are parsed in the background and added by poll_records_loader
         let plugin_paths = crate::get_plugins_sorted(&self.compare_data.path, false);
-        let mut plugins = Vec::new();
-        for path in plugin_paths.iter() {
-            if let Ok(plugin) = crate::parse_plugin(path) {
-                let filename = path.file_name().unwrap().to_str().unwrap();
-
-                plugins.push((filename, plugin));
-            }
-        }
+        self.records_data.loader = Some(PluginLoader::new(plugin_paths));
 
         let mut map: HashMap<String, HashMap<String, Vec<String>>> = HashMap::new();
         for tag in get_all_tags().iter() {
             map.insert(tag.to_string(), HashMap::new());
         }
 
-        for (plugin_name, plugin) in plugins.iter() {
-            for record in plugin.objects.iter() {
-                let id: String = record.editor_id().to_string();
-                let tag = record.tag_str().to_string();
-                if let Some(records) = map.get_mut(&tag) {
-                    if let Some(plugins) = records.get_mut(&id) {
-                        plugins.push(plugin_name.to_string());
-                    } else {
-                        records.insert(id, vec![plugin_name.to_string()]);
-                    }
+        self.records_data.records = map;
+        self.records_data.plugins.clear();
+        self.records_data.paths.clear();
+        self.records_data.cache.clear();
+        self.records_data.history = None;
+    }
+
+    /// Adds the plugins the loader finished to the Records view, in load order
+    pub(crate) fn poll_records_loader(&mut self) {
+        let Some(loader) = &mut self.records_data.loader else {
+            return;
+        };
+        let loaded = loader.poll();
+        let load_order = (0..loader.files.len())
+            .map(|i| (loader.get_name(i), i))
+            .collect::<HashMap<_, _>>();
+
+        for (index, loaded_plugin) in loaded {
+            let plugin_name = loader.get_name(index);
+            // the unique ids were created by the loader
+            for unique_id in loaded_plugin.records {
+                let Some((tag, id)) = unique_id.split_once(',') else {
+                    continue;
+                };
+                if let Some(records) = self.records_data.records.get_mut(tag) {
+                    let plugins = records.entry(id.to_owned()).or_default();
+                    let position = plugins
+                        .iter()
+                        .position(|n| load_order.get(n).is_some_and(|i| *i > index))
+                        .unwrap_or(plugins.len());
+                    plugins.insert(position, plugin_name.clone());
                 }
             }
+
+            let position = self
+                .records_data
+                .plugins
+                .iter()
+                .position(|(n, _)| load_order.get(n).is_some_and(|i| *i > index))
+                .unwrap_or(self.records_data.plugins.len());
+            self.records_data
+                .paths
+                .insert(plugin_name.clone(), loader.files[index].0.clone());
+            self.records_data
+                .plugins
+                .insert(position, (plugin_name, loaded_plugin.plugin));
+            self.records_data.cache.clear();
+            self.records_data.history = None;
         }
 
-        self.records_data.records = map;
+        if loader.is_finished() {
+            let failed = loader
+                .files
+                .iter()
+                .filter(|(_, s)| matches!(s, ELoadStatus::Failed(_)))
+                .count();
+            if failed > 0 {
+                self.toasts
+                    .warning(format!("{} plugins could not be loaded", failed));
+            }
+            self.records_data.loader = None;
+        }
     }
 }
 
@@ -239,6 +335,7 @@ impl eframe::App for TemplateApp {
                 EModalState::None => panic!("ArgumentException"),
                 EModalState::ModalCompareInit => self.update_modal_compare(ctx),
                 EModalState::Settings => self.update_settings(ctx),
+                EModalState::Heightmap => self.update_heightmap(ctx),
             }
         } else {
             // other main ui views
@@ -246,6 +343,10 @@ impl eframe::App for TemplateApp {
                 EAppState::SingleEdit => self.update_edit_view(ctx),
                 EAppState::Compare => self.update_compare_view(ctx, frame),
                 EAppState::Records => self.update_records_view(ctx),
+                EAppState::Map => self.update_map_view(ctx),
+                EAppState::Dialogue => self.update_dialogue_view(ctx),
+                EAppState::Quests => self.update_quest_view(ctx),
+                EAppState::Table => self.update_table_view(ctx),
             }
         }
 
@@ -1,6 +1,14 @@
 use tes3::esp::Plugin;
 
-use crate::{get_all_tags, EAppState, EModalState, TemplateApp};
+use crate::{
+    chains::check_info_chains, get_all_tags, views::loader_view::loader_view, EAppState,
+    EModalState, EQuerySource, TemplateApp,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    merge::{create_merged_plugin, get_merged_records},
+    sqlite::export_sqlite,
+};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -56,24 +64,12 @@ impl TemplateApp {
                 // View Menu
                 ui.menu_button("View", |ui| {
                     if self.use_experimental && ui.button("Compare View").clicked() {
-                        if !self.edit_data.plugins.is_empty() {
-                            self.toasts.warning(
-                                "Please close all open plugins before entering compare mode",
-                            );
-                        } else {
-                            self.open_modal_window(ui, EModalState::ModalCompareInit);
-                        }
+                        self.open_modal_window(ui, EModalState::ModalCompareInit);
                         ui.close_menu();
                     }
 
-                    if ui.button("Records View").clicked() {
-                        if !self.edit_data.plugins.is_empty() {
-                            self.toasts.warning(
-                                "Please close all open plugins before entering compare mode",
-                            );
-                        } else {
-                            self.app_state = EAppState::Records;
-                        }
+                    if ui.button("Edit View").clicked() {
+                        self.app_state = EAppState::SingleEdit;
                         ui.close_menu();
                     }
 
@@ -84,18 +80,138 @@ impl TemplateApp {
                         ui.close_menu();
                     }
                 });
+
+                // Tools Menu
+                ui.menu_button("Tools", |ui| {
+                    if ui.button("Check INFO chains").clicked() {
+                        let issues = check_info_chains(&self.records_data.plugins);
+                        if issues.is_empty() {
+                            self.toasts.success("No broken INFO chains found");
+                        }
+                        self.records_data.chain_issues = Some(issues);
+                        ui.close_menu();
+                    }
+                    if ui.button("Query records").clicked() {
+                        self.query_data.source = EQuerySource::LoadOrder;
+                        self.query_data.open = true;
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Export merged as JSON").clicked() {
+                        let some_path = rfd::FileDialog::new()
+                            .add_filter("json", &["json"])
+                            .set_file_name("merged.json")
+                            .save_file();
+                        if let Some(path) = some_path {
+                            let records = get_merged_records(&self.records_data.plugins);
+                            let result = serde_json::to_string_pretty(&records)
+                                .map_err(|e| e.to_string())
+                                .and_then(|text| {
+                                    std::fs::write(path, text).map_err(|e| e.to_string())
+                                });
+                            match result {
+                                Ok(_) => {
+                                    self.toasts.success(format!(
+                                        "Exported {} merged records",
+                                        records.len()
+                                    ));
+                                }
+                                Err(error) => {
+                                    self.toasts.error(format!("Could not export: {}", error));
+                                }
+                            }
+                        }
+                        ui.close_menu();
+                    }
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Export merged as plugin").clicked() {
+                        let some_path = rfd::FileDialog::new()
+                            .add_filter("esp", &["esp"])
+                            .set_directory(&self.compare_data.path)
+                            .set_file_name("merged.esp")
+                            .save_file();
+                        if let Some(path) = some_path {
+                            let records = get_merged_records(&self.records_data.plugins);
+                            let mut plugin = create_merged_plugin(&records);
+                            match plugin.save_path(path) {
+                                Ok(_) => {
+                                    self.toasts.success(format!(
+                                        "Merged plugin saved with {} records",
+                                        records.len()
+                                    ));
+                                }
+                                Err(_) => {
+                                    self.toasts.error("Could not save plugin");
+                                }
+                            }
+                        }
+                        ui.close_menu();
+                    }
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Export to SQLite").clicked() {
+                        let some_path = rfd::FileDialog::new()
+                            .add_filter("sqlite", &["sqlite", "db"])
+                            .set_file_name("load_order.sqlite")
+                            .save_file();
+                        if let Some(path) = some_path {
+                            let plugins = self
+                                .records_data
+                                .plugins
+                                .iter()
+                                .map(|(name, plugin)| {
+                                    (name.clone(), plugin.objects.iter().collect())
+                                })
+                                .collect::<Vec<_>>();
+                            match export_sqlite(&path, &plugins) {
+                                Ok(_) => {
+                                    self.toasts.success("Load order exported");
+                                }
+                                Err(error) => {
+                                    self.toasts.error(format!("Could not export: {}", error));
+                                }
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                });
             });
         });
 
+        // INFO chain check results
+        self.info_chains_window(ctx);
+        self.query_window(ctx);
+
         // load plugins
         if self.records_data.records.is_empty() {
             self.load_records();
         }
+        self.poll_records_loader();
+        if self.records_data.loader.is_some() {
+            ctx.request_repaint();
+        }
 
         // Side Panel
         let tags = get_all_tags();
 
+        // history of the selected record
+        egui::SidePanel::right("records_history")
+            .min_width(400.0)
+            .show(ctx, |ui| {
+                self.record_history_view(ui);
+            });
+
         egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(loader) = &self.records_data.loader {
+                if loader_view(ui, loader) {
+                    loader.cancel();
+                }
+            }
+
             // search bar
             let search_text = self.records_data.search_text.clone();
             ui.horizontal(|ui| {
@@ -149,9 +265,14 @@ impl TemplateApp {
                                 .unwrap();
 
                             ui.horizontal(|ui| {
-                                ui.label(id.clone());
+                                let key = format!("{},{}", tag, id);
+                                let is_selected =
+                                    self.records_data.selected_id.as_ref() == Some(&key);
+                                if ui.selectable_label(is_selected, id.clone()).clicked() {
+                                    self.records_data.selected_id = Some(key);
+                                }
                                 ui.separator();
-                                ui.label(format!("{:?}", plugins));
+                                ui.label(plugins.join(", "));
                             });
                         }
                     });
@@ -165,6 +286,11 @@ impl TemplateApp {
 
     /// Main single plugin edit view
     pub fn update_edit_view(&mut self, ctx: &egui::Context) {
+        self.edit_data.gmst_defaults.poll();
+        if self.edit_data.gmst_defaults.loader.is_some() {
+            ctx.request_repaint();
+        }
+
         // drag and drop
         self.ui_file_drag_and_drop(ctx);
 
@@ -224,6 +350,84 @@ impl TemplateApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             self.record_editor_view(ui);
         });
+
+        self.text_search_window(ctx);
+        self.query_window(ctx);
+    }
+
+    /// Exterior world map view
+    pub fn update_map_view(&mut self, ctx: &egui::Context) {
+        // Top Panel
+        egui::TopBottomPanel::top("top_panel_map").show(ctx, |ui| {
+            self.map_menu_bar_view(ui);
+        });
+
+        // Side Panel
+        egui::SidePanel::left("side_panel_map")
+            .min_width(250_f32)
+            .show(ctx, |ui| {
+                self.map_side_view(ui);
+            });
+
+        // Central Panel
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.map_view(ui);
+        });
+    }
+
+    /// Dialogue topics and their INFOs
+    pub fn update_dialogue_view(&mut self, ctx: &egui::Context) {
+        // Top Panel
+        egui::TopBottomPanel::top("top_panel_dialogue").show(ctx, |ui| {
+            self.dialogue_menu_bar_view(ui);
+        });
+
+        // Side Panel
+        egui::SidePanel::left("side_panel_dialogue")
+            .min_width(250_f32)
+            .show(ctx, |ui| {
+                self.dialogue_topics_view(ui);
+            });
+
+        // Central Panel
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.dialogue_infos_view(ui);
+        });
+    }
+
+    /// Records of one type as spreadsheet
+    pub fn update_table_view(&mut self, ctx: &egui::Context) {
+        // Top Panel
+        egui::TopBottomPanel::top("top_panel_table").show(ctx, |ui| {
+            self.table_menu_bar_view(ui);
+        });
+
+        // Central Panel
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.table_view(ui);
+        });
+
+        self.csv_import_window(ctx);
+    }
+
+    /// Journals and their stages
+    pub fn update_quest_view(&mut self, ctx: &egui::Context) {
+        // Top Panel
+        egui::TopBottomPanel::top("top_panel_quests").show(ctx, |ui| {
+            self.quest_menu_bar_view(ui);
+        });
+
+        // Side Panel
+        egui::SidePanel::left("side_panel_quests")
+            .min_width(250_f32)
+            .show(ctx, |ui| {
+                self.quest_list_view(ui);
+            });
+
+        // Central Panel
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.quest_view(ui);
+        });
     }
 
     /// Main compare view
@@ -233,6 +437,16 @@ impl TemplateApp {
             self.conflict_menu_bar_view(ui, frame);
         });
 
+        self.poll_compare_loader();
+        if let Some(loader) = &self.compare_data.loader {
+            ctx.request_repaint();
+            egui::TopBottomPanel::top("compare_loader").show(ctx, |ui| {
+                if loader_view(ui, loader) {
+                    loader.cancel();
+                }
+            });
+        }
+
         // Side Panel
         egui::SidePanel::left("side_panel_compare")
             .min_width(250_f32)
@@ -0,0 +1,432 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::Path,
+};
+
+use tes3::esp::{Dialogue, DialogueInfo, Header, ObjectFlags, Plugin, TES3Object};
+
+use crate::dialogue::get_chain_order;
+
+/// Kinds of broken links in an INFO chain
+#[derive(Debug, Clone, PartialEq)]
+pub enum EChainIssue {
+    /// the previous INFO does not exist in the topic
+    DanglingPrev(String),
+    /// the next INFO does not exist in the topic
+    DanglingNext(String),
+    /// the INFO links to another INFO that does not link back
+    Mismatch(String),
+    /// the INFO can't be reached from the start of the chain
+    Orphaned,
+    /// the INFO is part of a loop
+    Cycle,
+}
+
+impl Display for EChainIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EChainIssue::DanglingPrev(id) => write!(f, "previous INFO {} is missing", id),
+            EChainIssue::DanglingNext(id) => write!(f, "next INFO {} is missing", id),
+            EChainIssue::Mismatch(id) => write!(f, "INFO {} does not link back", id),
+            EChainIssue::Orphaned => write!(f, "not reachable from the start of the topic"),
+            EChainIssue::Cycle => write!(f, "part of a loop"),
+        }
+    }
+}
+
+/// A broken link of an INFO in the load order
+#[derive(Debug, Clone)]
+pub struct ChainIssue {
+    pub topic: String,
+    pub info_id: String,
+    /// the last plugin that touches the INFO
+    pub plugin: String,
+    pub kind: EChainIssue,
+}
+
+/// A topic merged over the load order
+#[derive(Default)]
+pub(crate) struct MergedTopic {
+    pub dialogue: Option<Dialogue>,
+    /// the winning INFOs and their plugin by INFO id, in order of appearance
+    pub infos: Vec<(DialogueInfo, String)>,
+}
+
+/// Merges all topics of the plugins in load order, later plugins win
+pub(crate) fn merge_topics(plugins: &[(String, Plugin)]) -> HashMap<String, MergedTopic> {
+    let mut topics: HashMap<String, MergedTopic> = HashMap::default();
+    for (plugin_name, plugin) in plugins {
+        let mut current = None;
+        for object in plugin.objects.iter() {
+            match object {
+                TES3Object::Dialogue(dialogue) => {
+                    let key = dialogue.id.to_lowercase();
+                    topics.entry(key.clone()).or_default().dialogue = Some(dialogue.clone());
+                    current = Some(key);
+                }
+                TES3Object::DialogueInfo(info) => {
+                    let Some(topic) = current.as_ref().and_then(|key| topics.get_mut(key)) else {
+                        continue;
+                    };
+                    match topic.infos.iter_mut().find(|(i, _)| i.id == info.id) {
+                        Some(existing) => *existing = (info.clone(), plugin_name.clone()),
+                        None => topic.infos.push((info.clone(), plugin_name.clone())),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // deleted INFOs are not part of the chain
+    for topic in topics.values_mut() {
+        topic
+            .infos
+            .retain(|(info, _)| !info.flags.contains(ObjectFlags::DELETED));
+    }
+    topics
+}
+
+/// Walks the INFO chain of every topic in load order and reports broken links
+pub fn check_info_chains(plugins: &[(String, Plugin)]) -> Vec<ChainIssue> {
+    let mut issues = vec![];
+    let topics = merge_topics(plugins);
+    let mut keys = topics.keys().collect::<Vec<_>>();
+    keys.sort();
+
+    for key in keys {
+        let topic = &topics[key];
+        let topic_name = topic
+            .dialogue
+            .as_ref()
+            .map(|d| d.id.clone())
+            .unwrap_or_else(|| key.clone());
+        let by_id = topic
+            .infos
+            .iter()
+            .map(|(info, _)| (info.id.as_str(), info))
+            .collect::<HashMap<_, _>>();
+        let mut add = |info: &DialogueInfo, plugin: &String, kind: EChainIssue| {
+            issues.push(ChainIssue {
+                topic: topic_name.clone(),
+                info_id: info.id.clone(),
+                plugin: plugin.clone(),
+                kind,
+            });
+        };
+
+        // links
+        for (info, plugin) in topic.infos.iter() {
+            if !info.prev_id.is_empty() {
+                match by_id.get(info.prev_id.as_str()) {
+                    None => add(
+                        info,
+                        plugin,
+                        EChainIssue::DanglingPrev(info.prev_id.clone()),
+                    ),
+                    Some(prev) if prev.next_id != info.id => {
+                        add(info, plugin, EChainIssue::Mismatch(prev.id.clone()))
+                    }
+                    _ => {}
+                }
+            }
+            if !info.next_id.is_empty() {
+                match by_id.get(info.next_id.as_str()) {
+                    None => add(
+                        info,
+                        plugin,
+                        EChainIssue::DanglingNext(info.next_id.clone()),
+                    ),
+                    Some(next) if next.prev_id != info.id => {
+                        add(info, plugin, EChainIssue::Mismatch(next.id.clone()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // walk the chain from its start
+        let mut visited = HashSet::new();
+        let mut cycles = HashSet::new();
+        let starts = topic
+            .infos
+            .iter()
+            .filter(|(info, _)| info.prev_id.is_empty())
+            .map(|(info, _)| info);
+        for start in starts {
+            let mut path = vec![];
+            let mut current = Some(start);
+            while let Some(info) = current {
+                if !visited.insert(info.id.as_str()) {
+                    // the walk came back to an INFO of this chain
+                    if let Some(position) = path.iter().position(|id| *id == info.id.as_str()) {
+                        cycles.extend(path.drain(position..));
+                    }
+                    break;
+                }
+                path.push(info.id.as_str());
+                current = by_id.get(info.next_id.as_str()).copied();
+            }
+        }
+        for (info, plugin) in topic.infos.iter() {
+            if cycles.contains(info.id.as_str()) {
+                add(info, plugin, EChainIssue::Cycle);
+                continue;
+            }
+            if visited.contains(info.id.as_str()) {
+                continue;
+            }
+            // unreachable INFOs either loop back to themselves or are cut off
+            let mut current = by_id.get(info.next_id.as_str()).copied();
+            let mut steps = 0;
+            let mut is_cycle = false;
+            while let Some(next) = current.filter(|_| steps <= topic.infos.len()) {
+                if next.id == info.id {
+                    is_cycle = true;
+                    break;
+                }
+                current = by_id.get(next.next_id.as_str()).copied();
+                steps += 1;
+            }
+            let kind = if is_cycle {
+                EChainIssue::Cycle
+            } else {
+                EChainIssue::Orphaned
+            };
+            add(info, plugin, kind);
+        }
+    }
+
+    issues
+}
+
+/// Creates a patch that relinks all INFOs of the broken topics into a single chain
+/// The chain keeps the order of the intact links, cut off INFOs are appended
+pub fn create_chain_patch(
+    plugins: &[(String, Plugin)],
+    folder: &Path,
+    issues: &[ChainIssue],
+) -> Plugin {
+    let topics = merge_topics(plugins);
+
+    // all loaded plugins are masters of the patch
+    let masters = plugins
+        .iter()
+        .map(|(name, _)| {
+            let size = std::fs::metadata(folder.join(name))
+                .map(|m| m.len())
+                .unwrap_or_default();
+            (name.clone(), size)
+        })
+        .collect::<Vec<_>>();
+    let header = Header {
+        version: 1.3,
+        description: "INFO chain repair patch".into(),
+        masters,
+        ..Default::default()
+    };
+    let mut objects = vec![TES3Object::Header(header)];
+
+    let mut broken_topics = issues
+        .iter()
+        .map(|i| i.topic.to_lowercase())
+        .collect::<Vec<_>>();
+    broken_topics.sort();
+    broken_topics.dedup();
+
+    for key in broken_topics {
+        let Some(topic) = topics.get(&key) else {
+            continue;
+        };
+        let Some(dialogue) = &topic.dialogue else {
+            continue;
+        };
+
+        let infos = topic.infos.iter().map(|(info, _)| info).collect::<Vec<_>>();
+        let order = get_chain_order(&infos);
+        let mut changed = vec![];
+        for (position, i) in order.iter().enumerate() {
+            let prev_id = match position {
+                0 => String::new(),
+                _ => infos[order[position - 1]].id.clone(),
+            };
+            let next_id = order
+                .get(position + 1)
+                .map(|n| infos[*n].id.clone())
+                .unwrap_or_default();
+            if infos[*i].prev_id != prev_id || infos[*i].next_id != next_id {
+                let mut info = infos[*i].clone();
+                info.prev_id = prev_id;
+                info.next_id = next_id;
+                changed.push(TES3Object::DialogueInfo(info));
+            }
+        }
+
+        if !changed.is_empty() {
+            objects.push(TES3Object::Dialogue(dialogue.clone()));
+            objects.extend(changed);
+        }
+    }
+
+    Plugin { objects }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, prev_id: &str, next_id: &str) -> DialogueInfo {
+        DialogueInfo {
+            id: id.into(),
+            prev_id: prev_id.into(),
+            next_id: next_id.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Checks a single topic and returns its issues by INFO id
+    fn check(infos: Vec<DialogueInfo>) -> Vec<(String, EChainIssue)> {
+        let mut objects = vec![TES3Object::Dialogue(Dialogue {
+            id: "topic".into(),
+            ..Default::default()
+        })];
+        objects.extend(infos.into_iter().map(TES3Object::DialogueInfo));
+        let plugins = vec![("test.esp".to_owned(), Plugin { objects })];
+        check_info_chains(&plugins)
+            .into_iter()
+            .map(|issue| (issue.info_id, issue.kind))
+            .collect()
+    }
+
+    fn issue(id: &str, kind: EChainIssue) -> (String, EChainIssue) {
+        (id.to_owned(), kind)
+    }
+
+    #[test]
+    fn intact_chain() {
+        let issues = check(vec![
+            info("a", "", "b"),
+            info("b", "a", "c"),
+            info("c", "b", ""),
+        ]);
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn dangling() {
+        let issues = check(vec![info("a", "", "b"), info("b", "a", "missing")]);
+        assert_eq!(
+            issues,
+            vec![issue("b", EChainIssue::DanglingNext("missing".into()))]
+        );
+
+        let issues = check(vec![info("a", "", ""), info("b", "missing", "")]);
+        assert_eq!(
+            issues,
+            vec![
+                issue("b", EChainIssue::DanglingPrev("missing".into())),
+                issue("b", EChainIssue::Orphaned),
+            ]
+        );
+    }
+
+    #[test]
+    fn orphaned() {
+        // c points back into the chain, but b does not continue to it
+        let issues = check(vec![
+            info("a", "", "b"),
+            info("b", "a", ""),
+            info("c", "b", ""),
+        ]);
+        assert_eq!(
+            issues,
+            vec![
+                issue("c", EChainIssue::Mismatch("b".into())),
+                issue("c", EChainIssue::Orphaned),
+            ]
+        );
+    }
+
+    #[test]
+    fn cycle_from_start() {
+        // a -> b -> c -> b
+        let issues = check(vec![
+            info("a", "", "b"),
+            info("b", "a", "c"),
+            info("c", "b", "b"),
+        ]);
+        assert_eq!(
+            issues,
+            vec![
+                issue("c", EChainIssue::Mismatch("b".into())),
+                issue("b", EChainIssue::Cycle),
+                issue("c", EChainIssue::Cycle),
+            ]
+        );
+    }
+
+    #[test]
+    fn unreachable_cycle() {
+        let issues = check(vec![
+            info("a", "", ""),
+            info("b", "c", "c"),
+            info("c", "b", "b"),
+        ]);
+        assert_eq!(
+            issues,
+            vec![
+                issue("b", EChainIssue::Cycle),
+                issue("c", EChainIssue::Cycle)
+            ]
+        );
+    }
+
+    #[test]
+    fn mismatch() {
+        // a links to b, but b starts its own chain
+        let issues = check(vec![info("a", "", "b"), info("b", "", "")]);
+        assert_eq!(issues, vec![issue("a", EChainIssue::Mismatch("b".into()))]);
+
+        // b links back to a, but a continues to c
+        let issues = check(vec![
+            info("a", "", "c"),
+            info("b", "a", ""),
+            info("c", "a", ""),
+        ]);
+        assert_eq!(
+            issues,
+            vec![
+                issue("b", EChainIssue::Mismatch("a".into())),
+                issue("b", EChainIssue::Orphaned),
+            ]
+        );
+    }
+
+    #[test]
+    fn later_plugin_wins() {
+        let topic = TES3Object::Dialogue(Dialogue {
+            id: "topic".into(),
+            ..Default::default()
+        });
+        let plugins = vec![
+            (
+                "master.esm".to_owned(),
+                Plugin {
+                    objects: vec![topic.clone(), TES3Object::DialogueInfo(info("a", "", ""))],
+                },
+            ),
+            (
+                "plugin.esp".to_owned(),
+                Plugin {
+                    objects: vec![
+                        topic,
+                        TES3Object::DialogueInfo(info("a", "", "b")),
+                        TES3Object::DialogueInfo(info("b", "a", "")),
+                    ],
+                },
+            ),
+        ];
+        assert!(check_info_chains(&plugins).is_empty());
+    }
+}
@@ -0,0 +1,542 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use egui_notify::Toasts;
+use tes3::esp::{Cell, Plugin, Reference, TES3Object, TypeInfo};
+
+use crate::{
+    check_info_topics, dialogue::sort_dialogue, get_unique_id, gmst::is_evil_gmst, parse_plugin,
+    PluginMetadata,
+};
+
+/// The result of cleaning a plugin
+#[derive(Default)]
+pub struct CleanReport {
+    /// The masters the plugin was compared against
+    pub masters: Vec<String>,
+    /// Masters listed in the header that could not be found
+    pub missing_masters: Vec<String>,
+    /// Unique ids of records that were identical to a master
+    pub identical_records: Vec<String>,
+    /// Unique ids of removed evil GMSTs
+    pub evil_gmsts: Vec<String>,
+    /// Cells and the number of unchanged master references removed from them
+    pub duplicate_references: Vec<(String, usize)>,
+}
+
+impl CleanReport {
+    /// Returns true if nothing was removed
+    pub fn is_clean(&self) -> bool {
+        self.identical_records.is_empty()
+            && self.evil_gmsts.is_empty()
+            && self.duplicate_references.is_empty()
+    }
+
+    /// Formats the report as plain text
+    pub fn to_text(&self, plugin_name: &str) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "Cleaning report for {}", plugin_name);
+        let _ = writeln!(text);
+
+        let _ = writeln!(text, "Masters:");
+        for master in self.masters.iter() {
+            let _ = writeln!(text, "  {}", master);
+        }
+        for master in self.missing_masters.iter() {
+            let _ = writeln!(text, "  {} (not found)", master);
+        }
+        let _ = writeln!(text);
+
+        let _ = writeln!(
+            text,
+            "Identical to master records removed: {}",
+            self.identical_records.len()
+        );
+        for id in self.identical_records.iter() {
+            let _ = writeln!(text, "  {}", id);
+        }
+        let _ = writeln!(text);
+
+        let _ = writeln!(text, "Evil GMSTs removed: {}", self.evil_gmsts.len());
+        for id in self.evil_gmsts.iter() {
+            let _ = writeln!(text, "  {}", id);
+        }
+        let _ = writeln!(text);
+
+        let count: usize = self.duplicate_references.iter().map(|(_, n)| n).sum();
+        let _ = writeln!(text, "Duplicate references removed: {}", count);
+        for (id, n) in self.duplicate_references.iter() {
+            let _ = writeln!(text, "  {}: {}", id, n);
+        }
+
+        text
+    }
+}
+
+/// Loads the masters listed in the plugin header from the given folder, in header order
+/// Masters that could not be loaded are returned by name
+pub fn load_masters<P>(masters: &[String], folder: P) -> (Vec<(String, Plugin)>, Vec<String>)
+where
+    P: AsRef<Path>,
+{
+    let mut loaded = vec![];
+    let mut missing = vec![];
+    for name in masters {
+        let path = folder.as_ref().join(name);
+        match parse_plugin(&path) {
+            Ok(plugin) => loaded.push((name.to_owned(), plugin)),
+            Err(_) => missing.push(name.to_owned()),
+        }
+    }
+    (loaded, missing)
+}
+
+/// Removes identical to master records, evil GMSTs and unchanged master references
+/// from the given records. Masters must be in header order.
+pub fn clean_records(
+    records: &mut HashMap<String, TES3Object>,
+    masters: &[(String, Plugin)],
+) -> CleanReport {
+    let mut report = CleanReport {
+        masters: masters.iter().map(|(name, _)| name.to_owned()).collect(),
+        ..Default::default()
+    };
+
+    // the last master to define a record wins
+    let mut master_records: HashMap<String, &TES3Object> = HashMap::default();
+    // the last master to change a reference wins, references are keyed by the master they come from
+    let mut master_references: HashMap<String, ReferenceMap<'_>> = HashMap::default();
+    for (name, plugin) in masters {
+        let master_names = get_master_names(plugin);
+        for record in plugin.objects.iter() {
+            let id = get_unique_id(record);
+            if let TES3Object::Cell(cell) = record {
+                let references = master_references.entry(id.clone()).or_default();
+                for (key, reference) in cell.references.iter() {
+                    if let Some(origin) = get_reference_origin(*key, name, &master_names) {
+                        references.insert(origin, reference);
+                    }
+                }
+            }
+            master_records.insert(id, record);
+        }
+    }
+    let plugin_masters = match records.get("TES3,") {
+        Some(TES3Object::Header(header)) => header
+            .masters
+            .iter()
+            .map(|(name, _)| name.to_owned())
+            .collect(),
+        _ => vec![],
+    };
+
+    let mut ids = records.keys().cloned().collect::<Vec<_>>();
+    ids.sort();
+
+    let mut to_remove = vec![];
+    for id in ids {
+        let record = records.get_mut(&id).unwrap();
+        match record.tag_str() {
+            // the header is never removed
+            "TES3" => {}
+            // infos are attached to the preceding topic, so topics are kept
+            "DIAL" => {}
+            "GMST" => {
+                if let TES3Object::GameSetting(gmst) = &*record {
+                    if is_evil_gmst(gmst) {
+                        report.evil_gmsts.push(id.clone());
+                        to_remove.push(id);
+                    } else if is_identical(&master_records, &id, record) {
+                        report.identical_records.push(id.clone());
+                        to_remove.push(id);
+                    }
+                }
+            }
+            "CELL" => {
+                let Some(master) = master_records.get(&id) else {
+                    continue;
+                };
+                let (TES3Object::Cell(cell), TES3Object::Cell(master_cell)) = (record, master)
+                else {
+                    continue;
+                };
+
+                let removed = master_references.get(&id).map_or(0, |references| {
+                    remove_duplicate_references(cell, references, &plugin_masters)
+                });
+                if removed > 0 {
+                    report.duplicate_references.push((id.clone(), removed));
+                }
+
+                // a cell without own references that does not change the master is an ITM
+                if cell.references.is_empty() {
+                    let mut a = cell.clone();
+                    let mut b = master_cell.clone();
+                    a.references.clear();
+                    b.references.clear();
+                    if a == b {
+                        report.identical_records.push(id.clone());
+                        to_remove.push(id);
+                    }
+                }
+            }
+            _ => {
+                if is_identical(&master_records, &id, record) {
+                    report.identical_records.push(id.clone());
+                    to_remove.push(id);
+                }
+            }
+        }
+    }
+
+    for id in to_remove {
+        records.remove(&id);
+    }
+
+    report
+}
+
+/// Returns true if the record is identical to the winning master record
+fn is_identical(
+    master_records: &HashMap<String, &TES3Object>,
+    id: &str,
+    record: &TES3Object,
+) -> bool {
+    master_records
+        .get(id)
+        .is_some_and(|master| *master == record)
+}
+
+/// References of a cell by the lowercase name of the plugin they come from and their index
+type ReferenceMap<'a> = HashMap<(String, u32), &'a Reference>;
+
+/// Names of the masters in the header of a plugin
+pub(crate) fn get_master_names(plugin: &Plugin) -> Vec<String> {
+    plugin
+        .objects
+        .iter()
+        .find_map(|record| match record {
+            TES3Object::Header(header) => Some(
+                header
+                    .masters
+                    .iter()
+                    .map(|(name, _)| name.to_owned())
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Resolves the key of a reference to the plugin that created it
+/// mast_index is 1-based into the header masters of the plugin, 0 are its own references
+pub(crate) fn get_reference_origin(
+    (mast_index, refr_index): (u32, u32),
+    plugin_name: &str,
+    master_names: &[String],
+) -> Option<(String, u32)> {
+    let origin = match mast_index {
+        0 => plugin_name,
+        i => master_names.get(i as usize - 1)?,
+    };
+    Some((origin.to_lowercase(), refr_index))
+}
+
+/// Removes references that are unchanged copies of the winning master reference and returns their count
+fn remove_duplicate_references(
+    cell: &mut Cell,
+    master_references: &ReferenceMap<'_>,
+    plugin_masters: &[String],
+) -> usize {
+    let mut duplicates = vec![];
+    for (key, reference) in cell.references.iter() {
+        // new references of the plugin have no master version
+        if key.0 == 0 {
+            continue;
+        }
+        let Some(master_reference) = get_reference_origin(*key, "", plugin_masters)
+            .and_then(|origin| master_references.get(&origin))
+        else {
+            continue;
+        };
+        let mut master_reference = (*master_reference).clone();
+        master_reference.mast_index = key.0;
+        if master_reference == *reference {
+            duplicates.push(*key);
+        }
+    }
+
+    for key in duplicates.iter() {
+        cell.references.remove(key);
+    }
+    duplicates.len()
+}
+
+/// Cleans a plugin against its masters, which are looked up in the folder of the plugin.
+/// Writes the cleaned plugin with clean.esp and the report with clean.txt as extension
+pub fn clean_plugin<P>(data: &PluginMetadata, plugin_path: P, toasts: &mut Toasts) -> bool
+where
+    P: AsRef<Path>,
+{
+    if !check_info_topics(data, toasts) {
+        return false;
+    }
+    // construct records from both lists
+    let mut records = data.records.clone();
+    for (id, record) in data.edited_records.iter() {
+        records.insert(id.clone(), record.clone());
+    }
+
+    let Some(TES3Object::Header(header)) = records.get("TES3,") else {
+        toasts.error("Plugin has no header");
+        return false;
+    };
+    let master_names = header
+        .masters
+        .iter()
+        .map(|(name, _)| name.to_owned())
+        .collect::<Vec<_>>();
+
+    let folder = plugin_path
+        .as_ref()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(PathBuf::new);
+    let (masters, missing_masters) = load_masters(&master_names, folder);
+    if !missing_masters.is_empty() {
+        toasts.warning(format!("Masters not found: {}", missing_masters.join(", ")));
+    }
+
+    let mut report = clean_records(&mut records, &masters);
+    report.missing_masters = missing_masters;
+
+    // header first
+    let header = records.remove("TES3,").unwrap();
+    let mut objects = vec![header];
+    objects.extend(sort_dialogue(records.into_values().collect(), &data.topics));
+    let mut plugin = Plugin { objects };
+
+    let plugin_name = plugin_path
+        .as_ref()
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let output_path = plugin_path.as_ref().with_extension("clean.esp");
+    let report_path = plugin_path.as_ref().with_extension("clean.txt");
+
+    if plugin.save_path(output_path).is_err() {
+        toasts.error("Could not save plugin");
+        return false;
+    }
+    if std::fs::write(report_path, report.to_text(&plugin_name)).is_err() {
+        toasts.error("Could not save report");
+        return false;
+    }
+
+    if report.is_clean() {
+        toasts.info("Plugin is already clean");
+    } else {
+        toasts.success(format!(
+            "Plugin cleaned: {} ITMs, {} evil GMSTs, {} references",
+            report.identical_records.len(),
+            report.evil_gmsts.len(),
+            report
+                .duplicate_references
+                .iter()
+                .map(|(_, n)| n)
+                .sum::<usize>()
+        ));
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use tes3::esp::{
+        CellData, CellFlags, GameSetting, GameSettingValue, Header, Reference, Weapon,
+    };
+
+    use super::*;
+    use crate::test_utils::{self, get_references};
+
+    fn header(masters: &[&str]) -> TES3Object {
+        TES3Object::Header(Header {
+            masters: masters.iter().map(|m| (m.to_string(), 0)).collect(),
+            ..Default::default()
+        })
+    }
+
+    fn reference((mast_index, refr_index): (u32, u32), id: &str, x: f32) -> Reference {
+        Reference {
+            translation: [x, 0.0, 0.0],
+            ..test_utils::reference(mast_index, refr_index, id)
+        }
+    }
+
+    fn cell(references: Vec<Reference>) -> TES3Object {
+        TES3Object::Cell(Cell {
+            name: "Balmora, Guild of Mages".into(),
+            data: CellData {
+                flags: CellFlags::IS_INTERIOR,
+                ..Default::default()
+            },
+            ..test_utils::cell(references)
+        })
+    }
+
+    fn weapon(id: &str, value: u32) -> TES3Object {
+        let mut weapon = Weapon {
+            id: id.into(),
+            ..Default::default()
+        };
+        weapon.data.value = value;
+        TES3Object::Weapon(weapon)
+    }
+
+    fn records(objects: Vec<TES3Object>) -> HashMap<String, TES3Object> {
+        objects
+            .into_iter()
+            .map(|record| (get_unique_id(&record), record))
+            .collect()
+    }
+
+    fn get_reference_keys(records: &HashMap<String, TES3Object>) -> Vec<(u32, u32)> {
+        let Some(TES3Object::Cell(cell)) = records.get("CELL,Balmora, Guild of Mages") else {
+            return vec![];
+        };
+        get_references(&cell.references)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    #[test]
+    fn identical_records() {
+        let masters = vec![(
+            "Morrowind.esm".to_string(),
+            Plugin {
+                objects: vec![
+                    header(&[]),
+                    weapon("iron dagger", 10),
+                    weapon("iron sword", 20),
+                ],
+            },
+        )];
+        let mut plugin = records(vec![
+            header(&["Morrowind.esm"]),
+            weapon("iron dagger", 10),
+            weapon("iron sword", 25),
+            weapon("new sword", 30),
+        ]);
+
+        let report = clean_records(&mut plugin, &masters);
+        assert_eq!(report.identical_records, vec!["WEAP,iron dagger"]);
+        let mut ids = plugin.keys().cloned().collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec!["TES3,", "WEAP,iron sword", "WEAP,new sword"]);
+    }
+
+    #[test]
+    fn evil_gmsts() {
+        let gmst = |id: &str, value: GameSettingValue| {
+            TES3Object::GameSetting(GameSetting {
+                id: id.into(),
+                value,
+                ..Default::default()
+            })
+        };
+        let mut plugin = records(vec![
+            header(&["Morrowind.esm"]),
+            gmst("sMaxSale", GameSettingValue::String("Max Sale".into())),
+            // a changed value is intended
+            gmst("fWereWolfRunMult", GameSettingValue::Float(2.0)),
+        ]);
+
+        let report = clean_records(&mut plugin, &[]);
+        assert_eq!(report.evil_gmsts, vec!["GMST,sMaxSale"]);
+        assert!(report.identical_records.is_empty());
+        assert!(plugin.contains_key("GMST,fWereWolfRunMult"));
+        assert!(!plugin.contains_key("GMST,sMaxSale"));
+    }
+
+    #[test]
+    fn duplicate_references_of_masters() {
+        let masters = vec![
+            (
+                "Morrowind.esm".to_string(),
+                Plugin {
+                    objects: vec![
+                        header(&[]),
+                        cell(vec![
+                            reference((0, 1), "table", 1.0),
+                            reference((0, 2), "chair", 2.0),
+                        ]),
+                    ],
+                },
+            ),
+            (
+                "Tribunal.esm".to_string(),
+                Plugin {
+                    objects: vec![
+                        header(&["Morrowind.esm"]),
+                        // moves the chair of Morrowind and adds a lamp
+                        cell(vec![
+                            reference((1, 2), "chair", 5.0),
+                            reference((0, 1), "lamp", 3.0),
+                        ]),
+                    ],
+                },
+            ),
+        ];
+        let mut plugin = records(vec![
+            header(&["Morrowind.esm", "Tribunal.esm"]),
+            cell(vec![
+                // unchanged table of Morrowind
+                reference((1, 1), "table", 1.0),
+                // the chair with the position of Morrowind reverts Tribunal
+                reference((1, 2), "chair", 2.0),
+                // unchanged lamp of Tribunal
+                reference((2, 1), "lamp", 3.0),
+                // a new reference of the plugin
+                reference((0, 1), "rug", 4.0),
+            ]),
+        ]);
+
+        let report = clean_records(&mut plugin, &masters);
+        assert_eq!(
+            report.duplicate_references,
+            vec![("CELL,Balmora, Guild of Mages".to_string(), 2)]
+        );
+        assert_eq!(get_reference_keys(&plugin), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn references_of_missing_masters() {
+        let masters = vec![(
+            "Morrowind.esm".to_string(),
+            Plugin {
+                objects: vec![header(&[]), cell(vec![reference((0, 1), "table", 1.0)])],
+            },
+        )];
+        // the second master was not found and the third is not in the header
+        let mut plugin = records(vec![
+            header(&["Morrowind.esm", "Missing.esp"]),
+            cell(vec![
+                reference((1, 1), "table", 1.0),
+                reference((2, 1), "table", 1.0),
+                reference((3, 1), "table", 1.0),
+            ]),
+        ]);
+
+        let report = clean_records(&mut plugin, &masters);
+        assert_eq!(
+            report.duplicate_references,
+            vec![("CELL,Balmora, Guild of Mages".to_string(), 1)]
+        );
+        assert_eq!(get_reference_keys(&plugin), vec![(2, 1), (3, 1)]);
+    }
+}
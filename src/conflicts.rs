@@ -0,0 +1,327 @@
+use std::{
+    collections::{hash_map::RandomState, BTreeSet, HashMap},
+    hash::BuildHasher,
+};
+
+use rayon::prelude::*;
+
+/// Which plugins contain each record, updated per plugin when plugins change
+#[derive(Default)]
+pub struct ConflictIndex {
+    /// ids of the indexed plugins in load order
+    order: Vec<u64>,
+    /// position of every indexed plugin in the load order
+    ranks: HashMap<u64, usize>,
+    /// record ids of every indexed plugin
+    records: HashMap<u64, Vec<String>>,
+    /// the plugins of every record id in load order
+    plugins_by_record: HashMap<String, Vec<u64>>,
+    /// record ids that are in more than one plugin, sorted
+    conflicts: BTreeSet<String>,
+}
+
+impl ConflictIndex {
+    fn update_ranks(&mut self) {
+        self.ranks = self
+            .order
+            .iter()
+            .enumerate()
+            .map(|(rank, id)| (*id, rank))
+            .collect();
+    }
+
+    /// Indexes all plugins at once, the plugins are in load order
+    ///
+    /// The ids of every plugin are bucketed by hash into one shard per thread in a single pass,
+    /// then every shard walks only its own buckets in load order
+    pub fn from_plugins(plugins: &[(u64, &[String])]) -> Self {
+        let shards = rayon::current_num_threads();
+        let hasher = RandomState::new();
+
+        // the record ids of every plugin and their positions per shard
+        let plugin_records = plugins
+            .par_iter()
+            .map(|(_, records)| {
+                let mut ids = records.to_vec();
+                // a record that is in a plugin twice is no conflict
+                ids.sort();
+                ids.dedup();
+                let mut buckets = vec![vec![]; shards];
+                for (i, id) in ids.iter().enumerate() {
+                    buckets[hasher.hash_one(id) as usize % shards].push(i);
+                }
+                (ids, buckets)
+            })
+            .collect::<Vec<_>>();
+
+        let plugins_by_record = (0..shards)
+            .into_par_iter()
+            .flat_map_iter(|shard| {
+                let mut map: HashMap<&str, Vec<u64>> = HashMap::new();
+                for ((plugin_id, _), (ids, buckets)) in plugins.iter().zip(plugin_records.iter()) {
+                    for i in buckets[shard].iter() {
+                        map.entry(ids[*i].as_str()).or_default().push(*plugin_id);
+                    }
+                }
+                map.into_iter()
+                    .map(|(record_id, plugins)| (record_id.to_owned(), plugins))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut index = Self {
+            order: plugins.iter().map(|(plugin_id, _)| *plugin_id).collect(),
+            conflicts: plugins_by_record
+                .iter()
+                .filter(|(_, plugins)| plugins.len() > 1)
+                .map(|(record_id, _)| record_id.clone())
+                .collect(),
+            records: plugins
+                .iter()
+                .zip(plugin_records)
+                .map(|((plugin_id, _), (ids, _))| (*plugin_id, ids))
+                .collect(),
+            plugins_by_record,
+            ..Default::default()
+        };
+        index.update_ranks();
+        index
+    }
+
+    pub fn contains_plugin(&self, plugin_id: u64) -> bool {
+        self.ranks.contains_key(&plugin_id)
+    }
+
+    /// Indexes a plugin at a position in the load order, other plugins are not read again
+    pub fn add_plugin(&mut self, plugin_id: u64, records: &[String], position: usize) {
+        if self.contains_plugin(plugin_id) {
+            self.remove_plugin(plugin_id);
+        }
+        self.order.insert(position.min(self.order.len()), plugin_id);
+        self.update_ranks();
+
+        let mut ids = records.to_vec();
+        // a record that is in a plugin twice is no conflict
+        ids.sort();
+        ids.dedup();
+        for record_id in ids.iter() {
+            let plugins = self.plugins_by_record.entry(record_id.clone()).or_default();
+            let rank = self.ranks[&plugin_id];
+            let index = plugins
+                .iter()
+                .position(|p| self.ranks[p] > rank)
+                .unwrap_or(plugins.len());
+            plugins.insert(index, plugin_id);
+            if plugins.len() == 2 {
+                self.conflicts.insert(record_id.clone());
+            }
+        }
+        self.records.insert(plugin_id, ids);
+    }
+
+    /// Removes a plugin from the index
+    pub fn remove_plugin(&mut self, plugin_id: u64) {
+        let Some(ids) = self.records.remove(&plugin_id) else {
+            return;
+        };
+        for record_id in ids.iter() {
+            let Some(plugins) = self.plugins_by_record.get_mut(record_id) else {
+                continue;
+            };
+            plugins.retain(|p| *p != plugin_id);
+            if plugins.len() < 2 {
+                self.conflicts.remove(record_id);
+            }
+            if plugins.is_empty() {
+                self.plugins_by_record.remove(record_id);
+            }
+        }
+        self.order.retain(|p| *p != plugin_id);
+        self.update_ranks();
+    }
+
+    /// Replaces the records of a plugin and keeps its place in the load order
+    pub fn reload_plugin(&mut self, plugin_id: u64, records: &[String]) {
+        let position = self
+            .ranks
+            .get(&plugin_id)
+            .copied()
+            .unwrap_or(self.order.len());
+        self.remove_plugin(plugin_id);
+        self.add_plugin(plugin_id, records, position);
+    }
+
+    /// Sets a new load order of the indexed plugins
+    /// Indexed plugins missing from the order keep their place after the others
+    pub fn set_order(&mut self, order: Vec<u64>) {
+        let mut new_order = order
+            .into_iter()
+            .filter(|id| self.records.contains_key(id))
+            .collect::<Vec<_>>();
+        for id in self.order.iter() {
+            if !new_order.contains(id) {
+                new_order.push(*id);
+            }
+        }
+        self.order = new_order;
+        self.update_ranks();
+
+        // only records of more than one plugin can change their order
+        for record_id in self.conflicts.iter() {
+            if let Some(plugins) = self.plugins_by_record.get_mut(record_id) {
+                plugins.sort_by_key(|p| self.ranks[p]);
+            }
+        }
+    }
+
+    /// The plugins of a record in load order if the record is in more than one plugin
+    pub fn get(&self, record_id: &str) -> Option<&Vec<u64>> {
+        self.conflicts
+            .get(record_id)
+            .and_then(|_| self.plugins_by_record.get(record_id))
+    }
+
+    /// All record ids that are in more than one plugin, sorted
+    pub fn get_conflicting_ids(&self) -> impl Iterator<Item = &String> {
+        self.conflicts.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.conflicts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(records: &[&str]) -> Vec<String> {
+        records.iter().map(|r| r.to_string()).collect()
+    }
+
+    #[test]
+    fn from_plugins_matches_added_plugins() {
+        let plugins = [
+            (1, ids(&["MISC,a", "MISC,b", "MISC,c"])),
+            (2, ids(&["MISC,b", "MISC,d", "MISC,d"])),
+            (3, ids(&["MISC,b", "MISC,c"])),
+        ];
+        let list = plugins
+            .iter()
+            .map(|(id, records)| (*id, records.as_slice()))
+            .collect::<Vec<_>>();
+
+        let index = ConflictIndex::from_plugins(&list);
+        let mut added = ConflictIndex::default();
+        for (position, (id, records)) in plugins.iter().enumerate() {
+            added.add_plugin(*id, records, position);
+        }
+
+        assert_eq!(
+            index.get_conflicting_ids().collect::<Vec<_>>(),
+            vec!["MISC,b", "MISC,c"]
+        );
+        assert_eq!(index.get("MISC,b"), Some(&vec![1, 2, 3]));
+        assert_eq!(index.get("MISC,c"), Some(&vec![1, 3]));
+        assert_eq!(index.get("MISC,d"), None);
+        for id in ["MISC,a", "MISC,b", "MISC,c", "MISC,d"] {
+            assert_eq!(index.get(id), added.get(id));
+        }
+        assert_eq!(index.plugins_by_record, added.plugins_by_record);
+        assert_eq!(index.records, added.records);
+        assert_eq!(index.order, added.order);
+    }
+
+    #[test]
+    fn add_remove_and_reload() {
+        let mut index = ConflictIndex::default();
+        index.add_plugin(1, &ids(&["MISC,a", "MISC,b"]), 0);
+        index.add_plugin(3, &ids(&["MISC,a"]), 1);
+        assert_eq!(index.get("MISC,a"), Some(&vec![1, 3]));
+        assert_eq!(index.get("MISC,b"), None);
+
+        // a plugin in between keeps the load order
+        index.add_plugin(2, &ids(&["MISC,a", "MISC,b"]), 1);
+        assert_eq!(index.get("MISC,a"), Some(&vec![1, 2, 3]));
+        assert_eq!(index.get("MISC,b"), Some(&vec![1, 2]));
+        assert_eq!(index.len(), 2);
+
+        index.remove_plugin(1);
+        assert!(!index.contains_plugin(1));
+        assert_eq!(index.get("MISC,a"), Some(&vec![2, 3]));
+        assert_eq!(index.get("MISC,b"), None);
+        assert_eq!(index.len(), 1);
+
+        // the reloaded plugin keeps its place
+        index.reload_plugin(2, &ids(&["MISC,c"]));
+        assert_eq!(index.order, vec![2, 3]);
+        assert!(index.is_empty());
+        index.reload_plugin(2, &ids(&["MISC,a"]));
+        assert_eq!(index.get("MISC,a"), Some(&vec![2, 3]));
+
+        index.remove_plugin(2);
+        index.remove_plugin(3);
+        assert!(index.is_empty());
+        assert!(index.plugins_by_record.is_empty());
+        assert!(index.order.is_empty());
+    }
+
+    #[test]
+    fn set_order_after_swap() {
+        let mut index = ConflictIndex::default();
+        index.add_plugin(1, &ids(&["MISC,a", "MISC,b"]), 0);
+        index.add_plugin(2, &ids(&["MISC,a"]), 1);
+        index.add_plugin(3, &ids(&["MISC,a", "MISC,b"]), 2);
+
+        index.set_order(vec![1, 3, 2]);
+        assert_eq!(index.get("MISC,a"), Some(&vec![1, 3, 2]));
+        assert_eq!(index.get("MISC,b"), Some(&vec![1, 3]));
+
+        // plugins that are not indexed are ignored, missing ones go last
+        index.set_order(vec![4, 3, 1]);
+        assert_eq!(index.order, vec![3, 1, 2]);
+        assert_eq!(index.get("MISC,a"), Some(&vec![3, 1, 2]));
+
+        // new plugins are placed by the new order
+        index.add_plugin(5, &ids(&["MISC,b"]), 1);
+        assert_eq!(index.get("MISC,b"), Some(&vec![3, 5, 1]));
+    }
+
+    #[test]
+    fn record_twice_in_plugin() {
+        let mut index = ConflictIndex::default();
+        index.add_plugin(1, &ids(&["MISC,a", "MISC,a"]), 0);
+        assert!(index.is_empty());
+        assert_eq!(index.plugins_by_record["MISC,a"], vec![1]);
+
+        index.add_plugin(2, &ids(&["MISC,a"]), 1);
+        assert_eq!(index.get("MISC,a"), Some(&vec![1, 2]));
+        index.remove_plugin(2);
+        assert!(index.is_empty());
+        assert_eq!(index.plugins_by_record["MISC,a"], vec![1]);
+    }
+
+    #[test]
+    fn record_of_one_plugin_leaves_conflicts() {
+        let mut index = ConflictIndex::default();
+        index.add_plugin(1, &ids(&["MISC,a", "MISC,b"]), 0);
+        index.add_plugin(2, &ids(&["MISC,a", "MISC,b"]), 1);
+        assert_eq!(index.len(), 2);
+
+        // only plugin 2 has the record after the reload
+        index.reload_plugin(1, &ids(&["MISC,b"]));
+        assert_eq!(index.get("MISC,a"), None);
+        assert_eq!(
+            index.get_conflicting_ids().collect::<Vec<_>>(),
+            vec!["MISC,b"]
+        );
+        assert_eq!(index.plugins_by_record["MISC,a"], vec![2]);
+
+        index.remove_plugin(2);
+        assert!(index.is_empty());
+        assert_eq!(index.plugins_by_record["MISC,b"], vec![1]);
+    }
+}
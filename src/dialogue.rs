@@ -0,0 +1,355 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use tes3::esp::{DialogueInfo, EditorId, Sex, TES3Object};
+
+use crate::{get_unique_id, PluginMetadata};
+
+/// Maps the unique id of every INFO to the unique id of the DIAL it follows in the plugin
+pub fn get_info_topics(objects: &[TES3Object]) -> HashMap<String, String> {
+    let mut topics = HashMap::default();
+    let mut current_topic = None;
+    for object in objects {
+        match object {
+            TES3Object::Dialogue(_) => current_topic = Some(get_unique_id(object)),
+            TES3Object::DialogueInfo(_) => {
+                if let Some(topic) = &current_topic {
+                    topics.insert(get_unique_id(object), topic.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    topics
+}
+
+/// Returns the order of INFOs by following their previous and next links
+/// INFOs that are not reachable from the start of a chain are appended by id
+pub fn get_chain_order(infos: &[&DialogueInfo]) -> Vec<usize> {
+    let by_id = infos
+        .iter()
+        .enumerate()
+        .map(|(i, info)| (info.id.as_str(), i))
+        .collect::<HashMap<_, _>>();
+
+    // chains start at an INFO with no previous INFO in this set
+    let mut starts = (0..infos.len())
+        .filter(|i| !by_id.contains_key(infos[*i].prev_id.as_str()))
+        .collect::<Vec<_>>();
+    starts.sort_by_key(|i| (!infos[*i].prev_id.is_empty(), infos[*i].id.clone()));
+
+    let mut order = vec![];
+    let mut visited = vec![false; infos.len()];
+    for start in starts {
+        let mut current = Some(start);
+        while let Some(i) = current.filter(|i| !visited[*i]) {
+            visited[i] = true;
+            order.push(i);
+            current = by_id.get(infos[i].next_id.as_str()).copied();
+        }
+    }
+
+    // cycles and broken links
+    let mut rest = (0..infos.len())
+        .filter(|i| !visited[*i])
+        .collect::<Vec<_>>();
+    rest.sort_by_key(|i| infos[*i].id.clone());
+    order.extend(rest);
+    order
+}
+
+/// Returns the unique ids of all INFOs of a topic in chain order
+pub fn get_topic_infos(data: &PluginMetadata, topic_id: &str) -> Vec<String> {
+    let mut ids = vec![];
+    let mut infos = vec![];
+    for (info_id, topic) in data.topics.iter() {
+        if topic != topic_id {
+            continue;
+        }
+        let record = data
+            .edited_records
+            .get(info_id)
+            .or_else(|| data.records.get(info_id));
+        if let Some(TES3Object::DialogueInfo(info)) = record {
+            ids.push(info_id.clone());
+            infos.push(info);
+        }
+    }
+
+    get_chain_order(&infos)
+        .into_iter()
+        .map(|i| ids[i].clone())
+        .collect()
+}
+
+/// Rewrites the previous and next links of the INFOs in the given order
+/// Links to INFOs outside of this plugin at both ends of the chain are kept
+pub fn relink_infos(data: &mut PluginMetadata, ordered_ids: &[String]) {
+    let get_info = |data: &PluginMetadata, id: &String| -> Option<DialogueInfo> {
+        match data.edited_records.get(id).or_else(|| data.records.get(id)) {
+            Some(TES3Object::DialogueInfo(info)) => Some(info.clone()),
+            _ => None,
+        }
+    };
+    let infos = ordered_ids
+        .iter()
+        .filter_map(|id| get_info(data, id).map(|info| (id.clone(), info)))
+        .collect::<Vec<_>>();
+
+    // external links at the chain ends
+    let ids = infos
+        .iter()
+        .map(|(_, info)| info.id.as_str())
+        .collect::<Vec<_>>();
+    let head_prev = infos
+        .iter()
+        .map(|(_, info)| info.prev_id.clone())
+        .find(|prev| !prev.is_empty() && !ids.contains(&prev.as_str()))
+        .unwrap_or_default();
+    let tail_next = infos
+        .iter()
+        .map(|(_, info)| info.next_id.clone())
+        .find(|next| !next.is_empty() && !ids.contains(&next.as_str()))
+        .unwrap_or_default();
+
+    let mut updates = vec![];
+    for (i, (unique_id, info)) in infos.iter().enumerate() {
+        let prev_id = if i == 0 {
+            head_prev.clone()
+        } else {
+            infos[i - 1].1.id.clone()
+        };
+        let next_id = if i + 1 == infos.len() {
+            tail_next.clone()
+        } else {
+            infos[i + 1].1.id.clone()
+        };
+        if info.prev_id != prev_id || info.next_id != next_id {
+            let mut info = info.clone();
+            info.prev_id = prev_id;
+            info.next_id = next_id;
+            updates.push((unique_id.clone(), info));
+        }
+    }
+    for (unique_id, info) in updates {
+        data.edited_records
+            .insert(unique_id, TES3Object::DialogueInfo(info));
+    }
+}
+
+/// Creates a new INFO in a topic at the given position and returns its unique id
+pub fn insert_info(data: &mut PluginMetadata, topic_id: &str, position: usize) -> Option<String> {
+    let dialogue_type = match data
+        .edited_records
+        .get(topic_id)
+        .or_else(|| data.records.get(topic_id))
+    {
+        Some(TES3Object::Dialogue(dialogue)) => dialogue.dialogue_type,
+        _ => return None,
+    };
+
+    // the construction set uses long random numbers as INFO ids
+    let mut seed = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let info = loop {
+        let mut info = DialogueInfo {
+            id: format!("{}", seed % 10u128.pow(19)),
+            ..Default::default()
+        };
+        info.data.dialogue_type = dialogue_type;
+        let object = TES3Object::DialogueInfo(info);
+        if !data.records.contains_key(&get_unique_id(&object))
+            && !data.edited_records.contains_key(&get_unique_id(&object))
+        {
+            break object;
+        }
+        seed += 1;
+    };
+
+    let mut ordered_ids = get_topic_infos(data, topic_id);
+    let unique_id = get_unique_id(&info);
+    data.edited_records.insert(unique_id.clone(), info);
+    data.topics.insert(unique_id.clone(), topic_id.to_owned());
+    ordered_ids.insert(position.min(ordered_ids.len()), unique_id.clone());
+    relink_infos(data, &ordered_ids);
+    data.clear_cache();
+
+    Some(unique_id)
+}
+
+/// Moves an INFO of a topic from one position to another
+pub fn move_info(data: &mut PluginMetadata, topic_id: &str, from: usize, to: usize) {
+    let mut ordered_ids = get_topic_infos(data, topic_id);
+    if from >= ordered_ids.len() {
+        return;
+    }
+    let id = ordered_ids.remove(from);
+    let to = if from < to { to - 1 } else { to };
+    ordered_ids.insert(to.min(ordered_ids.len()), id);
+    relink_infos(data, &ordered_ids);
+    data.clear_cache();
+}
+
+/// Returns the unique ids of all INFOs whose topic is not in the plugin
+/// An INFO belongs to the DIAL it follows, so these can't be saved
+pub fn get_infos_without_topic(data: &PluginMetadata) -> Vec<String> {
+    let mut ids = data
+        .records
+        .iter()
+        .chain(data.edited_records.iter())
+        .filter(|(_, record)| matches!(record, TES3Object::DialogueInfo(_)))
+        .filter(|(id, _)| {
+            !data.topics.get(*id).is_some_and(|topic| {
+                data.edited_records.contains_key(topic) || data.records.contains_key(topic)
+            })
+        })
+        .map(|(id, _)| id.clone())
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// Orders records so that every DIAL is followed by its INFOs in chain order
+/// INFOs without a topic are appended at the end, see [`get_infos_without_topic`]
+pub fn sort_dialogue(
+    objects: Vec<TES3Object>,
+    topics: &HashMap<String, String>,
+) -> Vec<TES3Object> {
+    let mut others = vec![];
+    let mut dialogues = vec![];
+    let mut infos: HashMap<String, Vec<DialogueInfo>> = HashMap::default();
+    let mut orphans = vec![];
+    for object in objects {
+        let unique_id = get_unique_id(&object);
+        match object {
+            TES3Object::Dialogue(_) => dialogues.push(object),
+            TES3Object::DialogueInfo(info) => match topics.get(&unique_id) {
+                Some(topic) => infos.entry(topic.clone()).or_default().push(info),
+                None => orphans.push(TES3Object::DialogueInfo(info)),
+            },
+            _ => others.push(object),
+        }
+    }
+    dialogues.sort_by(|a, b| a.editor_id().cmp(&b.editor_id()));
+
+    for dialogue in dialogues {
+        let topic_infos = infos.remove(&get_unique_id(&dialogue)).unwrap_or_default();
+        others.push(dialogue);
+
+        let order = get_chain_order(&topic_infos.iter().collect::<Vec<_>>());
+        let mut topic_infos = topic_infos.into_iter().map(Some).collect::<Vec<_>>();
+        for i in order {
+            if let Some(info) = topic_infos[i].take() {
+                others.push(TES3Object::DialogueInfo(info));
+            }
+        }
+    }
+
+    // INFOs of missing topics
+    for info in infos.into_values().flatten() {
+        orphans.push(TES3Object::DialogueInfo(info));
+    }
+    others.extend(orphans);
+    others
+}
+
+/// Readable speaker conditions of an INFO
+pub fn get_speaker_conditions(info: &DialogueInfo) -> Vec<String> {
+    let mut conditions = vec![];
+    let mut add = |name: &str, value: &str| {
+        if !value.is_empty() {
+            conditions.push(format!("{}: {}", name, value));
+        }
+    };
+    add("ID", &info.speaker_id);
+    add("Race", &info.speaker_race);
+    add("Class", &info.speaker_class);
+    add("Faction", &info.speaker_faction);
+    add("Cell", &info.speaker_cell);
+    add("PC Faction", &info.player_faction);
+
+    if info.data.speaker_rank >= 0 {
+        conditions.push(format!("Rank: {}", info.data.speaker_rank));
+    }
+    if info.data.player_rank >= 0 {
+        conditions.push(format!("PC Rank: {}", info.data.player_rank));
+    }
+    if info.data.speaker_sex != Sex::Any {
+        conditions.push(format!("Sex: {:?}", info.data.speaker_sex));
+    }
+    if info.data.disposition > 0 {
+        conditions.push(format!("Disposition: {}", info.data.disposition));
+    }
+    conditions
+}
+
+#[cfg(test)]
+mod tests {
+    use tes3::esp::Dialogue;
+
+    use super::*;
+
+    fn info(id: &str, prev_id: &str, next_id: &str) -> TES3Object {
+        TES3Object::DialogueInfo(DialogueInfo {
+            id: id.into(),
+            prev_id: prev_id.into(),
+            next_id: next_id.into(),
+            ..Default::default()
+        })
+    }
+
+    /// A plugin with one topic and its INFOs in chain order
+    fn plugin(infos: &[&str]) -> PluginMetadata {
+        let mut objects = vec![TES3Object::Dialogue(Dialogue {
+            id: "Balmora".into(),
+            ..Default::default()
+        })];
+        for (i, id) in infos.iter().enumerate() {
+            let prev = if i > 0 { infos[i - 1] } else { "" };
+            let next = infos.get(i + 1).copied().unwrap_or_default();
+            objects.push(info(id, prev, next));
+        }
+
+        let mut data = PluginMetadata::new("test.esp".into(), None);
+        data.topics = get_info_topics(&objects);
+        for object in objects {
+            data.records.insert(get_unique_id(&object), object);
+        }
+        data
+    }
+
+    #[test]
+    fn infos_without_topic() {
+        let mut data = plugin(&["1", "2"]);
+        assert!(get_infos_without_topic(&data).is_empty());
+
+        // added as a plain record
+        let record = info("3", "", "");
+        data.edited_records.insert(get_unique_id(&record), record);
+        // the topic was deleted
+        data.records.remove("DIAL,Balmora");
+        assert_eq!(
+            get_infos_without_topic(&data),
+            vec!["INFO,1", "INFO,2", "INFO,3"]
+        );
+    }
+
+    #[test]
+    fn move_info_relinks_and_clears_cache() {
+        let mut data = plugin(&["1", "2", "3"]);
+        data.cached_ids.insert("INFO".into(), vec![]);
+
+        move_info(&mut data, "DIAL,Balmora", 2, 0);
+        assert_eq!(
+            get_topic_infos(&data, "DIAL,Balmora"),
+            vec!["INFO,3", "INFO,1", "INFO,2"]
+        );
+        assert!(data.cached_ids.is_empty());
+        let Some(TES3Object::DialogueInfo(first)) = data.edited_records.get("INFO,3") else {
+            panic!("INFO 3 was not edited");
+        };
+        assert_eq!((first.prev_id.as_str(), first.next_id.as_str()), ("", "1"));
+    }
+}
@@ -0,0 +1,482 @@
+use std::fmt::Display;
+
+use strum::{Display, EnumIter};
+use tes3::esp::{Filter, FilterValue};
+
+/// Maximum number of filters of an INFO
+pub const MAX_FILTERS: usize = 6;
+
+/// Function names by their two digit code
+pub const FUNCTIONS: [&str; 74] = [
+    "ReactionLow",
+    "ReactionHigh",
+    "RankRequirement",
+    "Reputation",
+    "HealthPercent",
+    "PCReputation",
+    "PCLevel",
+    "PCHealthPercent",
+    "PCMagicka",
+    "PCFatigue",
+    "PCStrength",
+    "PCBlock",
+    "PCArmorer",
+    "PCMediumArmor",
+    "PCHeavyArmor",
+    "PCBluntWeapon",
+    "PCLongBlade",
+    "PCAxe",
+    "PCSpear",
+    "PCAthletics",
+    "PCEnchant",
+    "PCDestruction",
+    "PCAlteration",
+    "PCIllusion",
+    "PCConjuration",
+    "PCMysticism",
+    "PCRestoration",
+    "PCAlchemy",
+    "PCUnarmored",
+    "PCSecurity",
+    "PCSneak",
+    "PCAcrobatics",
+    "PCLightArmor",
+    "PCShortBlade",
+    "PCMarksman",
+    "PCMercantile",
+    "PCSpeechcraft",
+    "PCHandToHand",
+    "PCSex",
+    "PCExpelled",
+    "PCCommonDisease",
+    "PCBlightDisease",
+    "PCClothingModifier",
+    "PCCrimeLevel",
+    "SameSex",
+    "SameRace",
+    "SameFaction",
+    "FactionRankDiff",
+    "Detected",
+    "Alarmed",
+    "Choice",
+    "PCIntelligence",
+    "PCWillpower",
+    "PCAgility",
+    "PCSpeed",
+    "PCEndurance",
+    "PCPersonality",
+    "PCLuck",
+    "PCCorprus",
+    "Weather",
+    "PCVampire",
+    "Level",
+    "Attacked",
+    "TalkedToPC",
+    "PCHealth",
+    "CreatureTarget",
+    "FriendHit",
+    "Fight",
+    "Hello",
+    "Alarm",
+    "Flee",
+    "ShouldAttack",
+    "Werewolf",
+    "PCWerewolfKills",
+];
+
+/// Filter types, encoded as a single character
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter, Display)]
+pub enum EFilterType {
+    Function,
+    Global,
+    Local,
+    Journal,
+    Item,
+    Dead,
+    #[strum(to_string = "Not ID")]
+    NotId,
+    #[strum(to_string = "Not Faction")]
+    NotFaction,
+    #[strum(to_string = "Not Class")]
+    NotClass,
+    #[strum(to_string = "Not Race")]
+    NotRace,
+    #[strum(to_string = "Not Cell")]
+    NotCell,
+    #[strum(to_string = "Not Local")]
+    NotLocal,
+}
+
+impl EFilterType {
+    pub fn code(self) -> u8 {
+        match self {
+            EFilterType::Function => b'1',
+            EFilterType::Global => b'2',
+            EFilterType::Local => b'3',
+            EFilterType::Journal => b'4',
+            EFilterType::Item => b'5',
+            EFilterType::Dead => b'6',
+            EFilterType::NotId => b'7',
+            EFilterType::NotFaction => b'8',
+            EFilterType::NotClass => b'9',
+            EFilterType::NotRace => b'A',
+            EFilterType::NotCell => b'B',
+            EFilterType::NotLocal => b'C',
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        use strum::IntoEnumIterator;
+        EFilterType::iter().find(|t| t.code() == code)
+    }
+
+    /// Global and local variables have a variable type
+    pub fn is_variable(self) -> bool {
+        matches!(
+            self,
+            EFilterType::Global | EFilterType::Local | EFilterType::NotLocal
+        )
+    }
+
+    /// The function code of all types other than functions and variables
+    fn type_function(self) -> Option<&'static str> {
+        match self {
+            EFilterType::Journal => Some("JX"),
+            EFilterType::Item => Some("IX"),
+            EFilterType::Dead => Some("DX"),
+            EFilterType::NotId => Some("XX"),
+            EFilterType::NotFaction => Some("FX"),
+            EFilterType::NotClass => Some("CX"),
+            EFilterType::NotRace => Some("RX"),
+            EFilterType::NotCell => Some("LX"),
+            _ => None,
+        }
+    }
+}
+
+/// Variable types of global and local filters
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter, Display)]
+pub enum EVariableType {
+    Short,
+    Long,
+    Float,
+}
+
+impl EVariableType {
+    fn code(self) -> char {
+        match self {
+            EVariableType::Short => 's',
+            EVariableType::Long => 'l',
+            EVariableType::Float => 'f',
+        }
+    }
+
+    fn from_code(code: char) -> Option<Self> {
+        match code {
+            's' => Some(EVariableType::Short),
+            'l' => Some(EVariableType::Long),
+            'f' => Some(EVariableType::Float),
+            _ => None,
+        }
+    }
+}
+
+/// Comparison operators, encoded as a single digit
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter)]
+pub enum EComparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+}
+
+impl EComparison {
+    pub fn code(self) -> u8 {
+        b'0' + self as u8
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        use strum::IntoEnumIterator;
+        EComparison::iter().find(|c| c.code() == code)
+    }
+}
+
+impl Display for EComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            EComparison::Equal => "=",
+            EComparison::NotEqual => "!=",
+            EComparison::Greater => ">",
+            EComparison::GreaterEqual => ">=",
+            EComparison::Less => "<",
+            EComparison::LessEqual => "<=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// A readable dialogue filter condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedFilter {
+    pub filter_type: EFilterType,
+    /// index into [`FUNCTIONS`], only used by function filters
+    pub function: usize,
+    /// only used by variable filters
+    pub variable_type: EVariableType,
+    pub comparison: EComparison,
+    pub id: String,
+    pub value: FilterValue,
+}
+
+impl Default for DecodedFilter {
+    fn default() -> Self {
+        Self {
+            filter_type: EFilterType::Function,
+            function: 0,
+            variable_type: EVariableType::Short,
+            comparison: EComparison::Equal,
+            id: String::new(),
+            value: FilterValue::Integer(0),
+        }
+    }
+}
+
+impl Display for DecodedFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = if self.filter_type == EFilterType::Function {
+            FUNCTIONS[self.function]
+        } else {
+            &self.id
+        };
+        write!(
+            f,
+            "{}: {} {} {}",
+            self.filter_type,
+            name,
+            self.comparison,
+            format_filter_value(&self.value)
+        )
+    }
+}
+
+pub fn format_filter_value(value: &FilterValue) -> String {
+    match value {
+        FilterValue::Float(v) => v.to_string(),
+        FilterValue::Integer(v) => v.to_string(),
+    }
+}
+
+/// Decodes a filter, returns None if any part is unknown
+pub fn decode_filter(filter: &Filter) -> Option<DecodedFilter> {
+    let filter_type = EFilterType::from_code(filter.filter_type)?;
+    let comparison = EComparison::from_code(filter.comparison)?;
+    let mut decoded = DecodedFilter {
+        filter_type,
+        comparison,
+        id: filter.id.clone(),
+        value: filter.value,
+        ..Default::default()
+    };
+
+    if filter_type == EFilterType::Function {
+        decoded.function = filter
+            .function
+            .parse::<usize>()
+            .ok()
+            .filter(|f| *f < FUNCTIONS.len())?;
+    } else if filter_type.is_variable() {
+        let code = filter.function.chars().next()?;
+        decoded.variable_type = EVariableType::from_code(code)?;
+    }
+
+    Some(decoded)
+}
+
+/// Encodes a readable filter back into a filter, the index is kept
+pub fn encode_filter(decoded: &DecodedFilter, filter: &mut Filter) {
+    filter.filter_type = decoded.filter_type.code();
+    filter.function = if decoded.filter_type == EFilterType::Function {
+        format!("{:02}", decoded.function)
+    } else if decoded.filter_type.is_variable() {
+        format!("{}X", decoded.variable_type.code())
+    } else {
+        decoded
+            .filter_type
+            .type_function()
+            .unwrap_or_default()
+            .to_owned()
+    };
+    filter.comparison = decoded.comparison.code();
+    filter.id = if decoded.filter_type == EFilterType::Function {
+        String::new()
+    } else {
+        decoded.id.clone()
+    };
+    // float variables use float values
+    filter.value = match (decoded.filter_type.is_variable(), decoded.variable_type) {
+        (true, EVariableType::Float) => match decoded.value {
+            FilterValue::Integer(v) => FilterValue::Float(v as f32),
+            v => v,
+        },
+        _ => match decoded.value {
+            FilterValue::Float(v) => FilterValue::Integer(v as i32),
+            v => v,
+        },
+    };
+}
+
+/// Readable filter, or the raw encoded parts if it can't be decoded
+pub fn format_filter(filter: &Filter) -> String {
+    match decode_filter(filter) {
+        Some(decoded) => decoded.to_string(),
+        None => format!(
+            "{}{}{} {} {}",
+            filter.filter_type as char,
+            filter.function,
+            filter.comparison as char,
+            filter.id,
+            format_filter_value(&filter.value)
+        ),
+    }
+}
+
+/// The smallest filter index that is not used yet, None if all are used
+pub fn get_free_filter_index(filters: &[Filter]) -> Option<u8> {
+    (0..MAX_FILTERS as u8).find(|index| !filters.iter().any(|f| f.index == *index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits a SCVR string into a filter: index, type, function, comparison and id
+    fn parse_scvr(scvr: &str, value: FilterValue) -> Filter {
+        let bytes = scvr.as_bytes();
+        Filter {
+            index: bytes[0] - b'0',
+            filter_type: bytes[1],
+            function: scvr[2..4].to_owned(),
+            comparison: bytes[4],
+            id: scvr[5..].to_owned(),
+            value,
+        }
+    }
+
+    fn to_scvr(filter: &Filter) -> String {
+        format!(
+            "{}{}{}{}{}",
+            (filter.index + b'0') as char,
+            filter.filter_type as char,
+            filter.function,
+            filter.comparison as char,
+            filter.id
+        )
+    }
+
+    /// Decodes and encodes a filter and checks that nothing changed
+    fn round_trip(scvr: &str, value: FilterValue) -> DecodedFilter {
+        let filter = parse_scvr(scvr, value);
+        let decoded = decode_filter(&filter).expect(scvr);
+        let mut encoded = Filter {
+            index: filter.index,
+            ..Default::default()
+        };
+        encode_filter(&decoded, &mut encoded);
+        assert_eq!(to_scvr(&encoded), scvr);
+        assert_eq!(encoded, filter);
+        decoded
+    }
+
+    #[test]
+    fn function() {
+        let decoded = round_trip("01500", FilterValue::Integer(1));
+        assert_eq!(FUNCTIONS[decoded.function], "Choice");
+        assert_eq!(decoded.comparison, EComparison::Equal);
+
+        let decoded = round_trip("11392", FilterValue::Integer(0));
+        assert_eq!(FUNCTIONS[decoded.function], "PCExpelled");
+        assert_eq!(decoded.comparison, EComparison::Greater);
+    }
+
+    #[test]
+    fn variables() {
+        let decoded = round_trip("02sX0PCVampire", FilterValue::Integer(1));
+        assert_eq!(decoded.filter_type, EFilterType::Global);
+        assert_eq!(decoded.variable_type, EVariableType::Short);
+        assert_eq!(decoded.id, "PCVampire");
+
+        let decoded = round_trip("13fX4GameHour", FilterValue::Float(6.5));
+        assert_eq!(decoded.filter_type, EFilterType::Local);
+        assert_eq!(decoded.variable_type, EVariableType::Float);
+        assert_eq!(decoded.comparison, EComparison::Less);
+
+        let decoded = round_trip("2ClX0NoLore", FilterValue::Integer(0));
+        assert_eq!(decoded.filter_type, EFilterType::NotLocal);
+        assert_eq!(decoded.variable_type, EVariableType::Long);
+    }
+
+    #[test]
+    fn other_types() {
+        for (scvr, filter_type) in [
+            ("04JX3A1_1_FindSpymaster", EFilterType::Journal),
+            ("05IX2Gold_001", EFilterType::Item),
+            ("06DX3dagoth_ur_1", EFilterType::Dead),
+            ("07XX0fargoth", EFilterType::NotId),
+            ("08FX0Hlaalu", EFilterType::NotFaction),
+            ("09CX0Guard", EFilterType::NotClass),
+            ("0ARX0Dark Elf", EFilterType::NotRace),
+            ("0BLX0Balmora", EFilterType::NotCell),
+        ] {
+            let decoded = round_trip(scvr, FilterValue::Integer(1));
+            assert_eq!(decoded.filter_type, filter_type);
+            assert_eq!(decoded.id, scvr[5..]);
+        }
+    }
+
+    #[test]
+    fn unknown_codes() {
+        for scvr in ["0D000", "01990", "0100X", "02xX0PCVampire"] {
+            assert_eq!(
+                decode_filter(&parse_scvr(scvr, FilterValue::Integer(0))),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn value_types() {
+        // float variables always get float values
+        let mut decoded = round_trip("02fX0GameHour", FilterValue::Float(0.0));
+        decoded.value = FilterValue::Integer(12);
+        let mut filter = Filter::default();
+        encode_filter(&decoded, &mut filter);
+        assert_eq!(filter.value, FilterValue::Float(12.0));
+
+        // everything else is compared to integers
+        let mut decoded = round_trip("05IX2Gold_001", FilterValue::Integer(0));
+        decoded.value = FilterValue::Float(100.7);
+        encode_filter(&decoded, &mut filter);
+        assert_eq!(filter.value, FilterValue::Integer(100));
+    }
+
+    #[test]
+    fn free_filter_index() {
+        let filters = |indices: &[u8]| {
+            indices
+                .iter()
+                .map(|index| Filter {
+                    index: *index,
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(get_free_filter_index(&[]), Some(0));
+        assert_eq!(get_free_filter_index(&filters(&[0, 1, 2])), Some(3));
+        // the first filter was removed
+        assert_eq!(get_free_filter_index(&filters(&[1, 2, 3, 4, 5])), Some(0));
+        assert_eq!(get_free_filter_index(&filters(&[0, 1, 3, 4, 5])), Some(2));
+        assert_eq!(get_free_filter_index(&filters(&[0, 1, 2, 3, 4, 5])), None);
+    }
+}
@@ -0,0 +1,304 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use tes3::esp::{GameSetting, GameSettingValue, TES3Object};
+
+use crate::{loader::PluginLoader, PluginMetadata};
+
+/// The masters that define the vanilla GMSTs, in load order
+const VANILLA_MASTERS: [&str; 3] = ["Morrowind.esm", "Tribunal.esm", "Bloodmoon.esm"];
+
+/// A built-in GMST default value
+pub enum GmstDefault {
+    Integer(i32),
+    Float(f32),
+    String(&'static str),
+}
+
+impl GmstDefault {
+    /// The default as a GMST value
+    pub fn to_value(&self) -> GameSettingValue {
+        match self {
+            GmstDefault::Integer(v) => GameSettingValue::Integer(*v),
+            GmstDefault::Float(v) => GameSettingValue::Float(*v),
+            GmstDefault::String(v) => GameSettingValue::String(v.to_string()),
+        }
+    }
+}
+
+/// A vanilla GMST and its default value
+pub struct VanillaGmst {
+    pub name: &'static str,
+    pub value: GmstDefault,
+    /// The Construction Set silently adds this GMST to a plugin when Tribunal or Bloodmoon are loaded
+    pub evil: bool,
+}
+
+const fn vanilla(name: &'static str, value: GmstDefault) -> VanillaGmst {
+    VanillaGmst {
+        name,
+        value,
+        evil: false,
+    }
+}
+
+const fn evil(name: &'static str, value: GmstDefault) -> VanillaGmst {
+    VanillaGmst {
+        name,
+        value,
+        evil: true,
+    }
+}
+
+/// Built-in GMST defaults, used for the evil GMST detection and when the vanilla masters are not found
+/// the evil GMSTs are taken from the tes3cmd clean command
+const VANILLA_GMSTS: &[VanillaGmst] = &[
+    // Morrowind
+    vanilla("fFatigueBase", GmstDefault::Float(1.25)),
+    vanilla("fFatigueMult", GmstDefault::Float(0.5)),
+    vanilla("fMajorSkillBonus", GmstDefault::Float(0.75)),
+    vanilla("fMinorSkillBonus", GmstDefault::Float(1.0)),
+    vanilla("fMiscSkillBonus", GmstDefault::Float(1.25)),
+    vanilla("fNPCbaseMagickaMult", GmstDefault::Float(2.0)),
+    vanilla("fPCbaseMagickaMult", GmstDefault::Float(1.0)),
+    vanilla("iLevelupTotal", GmstDefault::Integer(10)),
+    vanilla("iMaxActivateDist", GmstDefault::Integer(192)),
+    // Tribunal
+    evil("sCompanionShare", GmstDefault::String("Companion Share")),
+    evil("sCompanionWarningButtonOne", GmstDefault::String("Let the mercenary quit.")),
+    evil("sCompanionWarningButtonTwo", GmstDefault::String("Return to Companion Share display.")),
+    evil("sCompanionWarningMessage", GmstDefault::String("Your mercenary is poorer now than when he contracted with you.  Your mercenary will quit if you do not give him gold or goods to bring his Profit Value to a positive value.")),
+    evil("sDeleteNote", GmstDefault::String("Delete Note?")),
+    evil("sEditNote", GmstDefault::String("Edit Note")),
+    evil("sLevitateDisabled", GmstDefault::String("Levitation magic does not work here.")),
+    evil("sMaxSale", GmstDefault::String("Max Sale")),
+    evil("sProfitValue", GmstDefault::String("Profit Value")),
+    evil("sTeleportDisabled", GmstDefault::String("Teleportation magic does not work here.")),
+    evil("fNPCHealthBarFade", GmstDefault::Float(1.0)),
+    // Bloodmoon
+    evil("fCombatDistanceWerewolfMod", GmstDefault::Float(0.3)),
+    evil("fFleeDistance", GmstDefault::Float(3000.0)),
+    evil("fWereWolfAcrobatics", GmstDefault::Float(80.0)),
+    evil("fWereWolfAgility", GmstDefault::Float(150.0)),
+    evil("fWereWolfAlchemy", GmstDefault::Float(1.0)),
+    evil("fWereWolfAlteration", GmstDefault::Float(1.0)),
+    evil("fWereWolfArmorer", GmstDefault::Float(1.0)),
+    evil("fWereWolfAthletics", GmstDefault::Float(150.0)),
+    evil("fWereWolfAxe", GmstDefault::Float(1.0)),
+    evil("fWereWolfBlock", GmstDefault::Float(1.0)),
+    evil("fWereWolfBluntWeapon", GmstDefault::Float(1.0)),
+    evil("fWereWolfConjuration", GmstDefault::Float(1.0)),
+    evil("fWereWolfDestruction", GmstDefault::Float(1.0)),
+    evil("fWereWolfEnchant", GmstDefault::Float(1.0)),
+    evil("fWereWolfEndurance", GmstDefault::Float(150.0)),
+    evil("fWereWolfFatigue", GmstDefault::Float(400.0)),
+    evil("fWereWolfHandtoHand", GmstDefault::Float(100.0)),
+    evil("fWereWolfHealth", GmstDefault::Float(2.0)),
+    evil("fWereWolfHeavyArmor", GmstDefault::Float(1.0)),
+    evil("fWereWolfIllusion", GmstDefault::Float(1.0)),
+    evil("fWereWolfIntellegence", GmstDefault::Float(1.0)),
+    evil("fWereWolfLightArmor", GmstDefault::Float(1.0)),
+    evil("fWereWolfLongBlade", GmstDefault::Float(1.0)),
+    evil("fWereWolfLuck", GmstDefault::Float(1.0)),
+    evil("fWereWolfMagicka", GmstDefault::Float(100.0)),
+    evil("fWereWolfMarksman", GmstDefault::Float(1.0)),
+    evil("fWereWolfMediumArmor", GmstDefault::Float(1.0)),
+    evil("fWereWolfMerchantile", GmstDefault::Float(1.0)),
+    evil("fWereWolfMysticism", GmstDefault::Float(1.0)),
+    evil("fWereWolfPersonality", GmstDefault::Float(1.0)),
+    evil("fWereWolfRestoration", GmstDefault::Float(1.0)),
+    evil("fWereWolfRunMult", GmstDefault::Float(1.5)),
+    evil("fWereWolfSecurity", GmstDefault::Float(1.0)),
+    evil("fWereWolfShortBlade", GmstDefault::Float(1.0)),
+    evil("fWereWolfSilverWeaponDamageMult", GmstDefault::Float(1.5)),
+    evil("fWereWolfSneak", GmstDefault::Float(1.0)),
+    evil("fWereWolfSpear", GmstDefault::Float(1.0)),
+    evil("fWereWolfSpeechcraft", GmstDefault::Float(1.0)),
+    evil("fWereWolfSpeed", GmstDefault::Float(150.0)),
+    evil("fWereWolfStrength", GmstDefault::Float(150.0)),
+    evil("fWereWolfUnarmored", GmstDefault::Float(1.0)),
+    evil("fWereWolfWillPower", GmstDefault::Float(1.0)),
+    evil("iWereWolfBounty", GmstDefault::Integer(1000)),
+    evil("iWereWolfFightMod", GmstDefault::Integer(100)),
+    evil("iWereWolfFleeMod", GmstDefault::Integer(100)),
+    evil("iWereWolfLevelToAttack", GmstDefault::Integer(20)),
+    evil("sWerewolfAlarmMessage", GmstDefault::String("You have been detected changing from a werewolf state.")),
+    evil("sWerewolfPopup", GmstDefault::String("Werewolf")),
+    evil("sWerewolfRefusal", GmstDefault::String("You cannot do this as a werewolf.")),
+    evil("sWerewolfRestMessage", GmstDefault::String("You cannot rest in werewolf form.")),
+];
+
+/// Looks up the vanilla default of a GMST by name
+pub fn get_vanilla_gmst(name: &str) -> Option<&'static VanillaGmst> {
+    VANILLA_GMSTS
+        .iter()
+        .find(|e| e.name.eq_ignore_ascii_case(name))
+}
+
+/// Returns true if two GMST values are of the same type and equal
+pub fn is_same_value(a: &GameSettingValue, b: &GameSettingValue) -> bool {
+    match (a, b) {
+        (GameSettingValue::Integer(a), GameSettingValue::Integer(b)) => a == b,
+        (GameSettingValue::Float(a), GameSettingValue::Float(b)) => (a - b).abs() < f32::EPSILON,
+        (GameSettingValue::String(a), GameSettingValue::String(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Returns true if the GMST has its vanilla default value
+/// Such a GMST silently resets changes made by any plugin loaded before it
+pub fn is_default_gmst(gmst: &GameSetting) -> bool {
+    get_vanilla_gmst(&gmst.id)
+        .is_some_and(|vanilla| is_same_value(&vanilla.value.to_value(), &gmst.value))
+}
+
+/// Returns true if the GMST is one of the evil GMSTs and still has the Construction Set value
+pub fn is_evil_gmst(gmst: &GameSetting) -> bool {
+    get_vanilla_gmst(&gmst.id).is_some_and(|e| e.evil) && is_default_gmst(gmst)
+}
+
+/// Formats a GMST value for display
+pub fn format_gmst_value(value: &GameSettingValue) -> String {
+    match value {
+        GameSettingValue::Integer(v) => format!("{}", v),
+        GameSettingValue::Float(v) => format!("{:.4}", v),
+        GameSettingValue::String(v) => format!("\"{}\"", v),
+    }
+}
+
+/// GMST values of the vanilla masters in a data folder
+#[derive(Default)]
+pub struct GmstDefaults {
+    /// the folder the masters are read from
+    folder: Option<PathBuf>,
+    pub loader: Option<PluginLoader>,
+    /// GMSTs of every finished master by its load order
+    masters: Vec<(usize, Vec<GameSetting>)>,
+    /// the winning values by lowercase name
+    values: HashMap<String, GameSettingValue>,
+}
+
+impl GmstDefaults {
+    /// Reads the GMSTs of the vanilla masters in the background, once per data folder
+    /// A folder without masters only uses the built-in table
+    pub fn load(&mut self, folder: &Path) {
+        if self.folder.as_deref() == Some(folder) {
+            return;
+        }
+        let paths = VANILLA_MASTERS
+            .iter()
+            .map(|name| folder.join(name))
+            .filter(|path| path.exists())
+            .collect::<Vec<_>>();
+
+        *self = Self {
+            folder: Some(folder.to_path_buf()),
+            loader: (!paths.is_empty()).then(|| PluginLoader::new(paths)),
+            ..Default::default()
+        };
+    }
+
+    /// Adds the GMSTs of the masters the loader finished
+    pub fn poll(&mut self) {
+        let Some(loader) = &mut self.loader else {
+            return;
+        };
+        let loaded = loader.poll();
+        if loader.is_finished() {
+            self.loader = None;
+        }
+        if loaded.is_empty() {
+            return;
+        }
+
+        for (index, loaded_plugin) in loaded {
+            let gmsts = loaded_plugin
+                .plugin
+                .objects
+                .into_iter()
+                .filter_map(|record| match record {
+                    TES3Object::GameSetting(gmst) => Some(gmst),
+                    _ => None,
+                })
+                .collect();
+            self.masters.push((index, gmsts));
+        }
+
+        // the last master wins
+        self.masters.sort_by_key(|(index, _)| *index);
+        self.values = self
+            .masters
+            .iter()
+            .flat_map(|(_, gmsts)| gmsts.iter())
+            .map(|gmst| (gmst.id.to_lowercase(), gmst.value.clone()))
+            .collect();
+    }
+
+    /// The vanilla value of a GMST, from the masters if they were read or from the built-in table
+    pub fn get(&self, name: &str) -> Option<GameSettingValue> {
+        match self.values.get(&name.to_lowercase()) {
+            Some(value) => Some(value.clone()),
+            None => get_vanilla_gmst(name).map(|vanilla| vanilla.value.to_value()),
+        }
+    }
+}
+
+/// Returns the unique ids of all evil GMSTs in a plugin
+pub fn get_evil_gmsts(data: &PluginMetadata) -> Vec<String> {
+    let mut ids = data
+        .records
+        .iter()
+        .chain(data.edited_records.iter())
+        .filter_map(|(id, record)| match record {
+            tes3::esp::TES3Object::GameSetting(gmst) if is_evil_gmst(gmst) => Some(id.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_values() {
+        use GameSettingValue::*;
+        assert!(is_same_value(&Float(1.0), &Float(1.0)));
+        assert!(!is_same_value(&Float(1.0), &Float(1.0001)));
+        assert!(!is_same_value(&Float(1.0), &Integer(1)));
+        assert!(is_same_value(
+            &String("Max Sale".into()),
+            &String("Max Sale".into())
+        ));
+        assert!(!is_same_value(
+            &String("Max Sale".into()),
+            &String("max sale".into())
+        ));
+    }
+
+    #[test]
+    fn folder_without_masters() {
+        let mut defaults = GmstDefaults {
+            folder: Some(PathBuf::from("previous")),
+            values: [("smaxsale".to_string(), GameSettingValue::String("x".into()))]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+
+        let folder = std::env::temp_dir().join("tes3edit_no_masters");
+        defaults.load(&folder);
+        assert_eq!(defaults.folder.as_deref(), Some(folder.as_path()));
+        assert!(defaults.loader.is_none());
+        assert_eq!(
+            defaults.get("sMaxSale"),
+            Some(GameSettingValue::String("Max Sale".into()))
+        );
+        assert_eq!(defaults.get("sUnknown"), None);
+    }
+}
@@ -0,0 +1,446 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Error, ErrorKind, Read, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+use tes3::esp::{Landscape, LandscapeFlags, TES3Object, VertexHeights};
+
+use crate::PluginMetadata;
+
+/// Vertices per cell side, neighbouring cells share their edge vertices
+const VERTICES: usize = 65;
+/// Pixels per cell in an exported heightmap
+const CELL_PIXELS: usize = VERTICES - 1;
+/// Distance between two vertices in game units
+const VERTEX_SPACING: f32 = 128.0;
+/// Heights are stored in units of 8 game units
+const HEIGHT_SCALE: f32 = 8.0;
+
+pub type Heights = Box<[[i32; VERTICES]; VERTICES]>;
+
+#[derive(Default, EnumIter, Debug, Clone, Copy, PartialEq, Display)]
+pub enum EHeightmapFormat {
+    /// 16-bit grayscale png
+    #[default]
+    Png,
+    /// 16-bit little endian raw
+    Raw,
+}
+
+impl EHeightmapFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            EHeightmapFormat::Png => "png",
+            EHeightmapFormat::Raw => "raw",
+        }
+    }
+}
+
+/// Sidecar of an exported heightmap, needed to import it again
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeightmapInfo {
+    /// the south-west cell
+    pub min: (i32, i32),
+    /// the north-east cell
+    pub max: (i32, i32),
+}
+
+impl HeightmapInfo {
+    pub fn width(&self) -> usize {
+        (self.max.0 - self.min.0 + 1) as usize * CELL_PIXELS + 1
+    }
+    pub fn height(&self) -> usize {
+        (self.max.1 - self.min.1 + 1) as usize * CELL_PIXELS + 1
+    }
+
+    pub fn contains(&self, grid: (i32, i32)) -> bool {
+        grid.0 >= self.min.0 && grid.0 <= self.max.0 && grid.1 >= self.min.1 && grid.1 <= self.max.1
+    }
+
+    /// Pixel position of a vertex of a cell, north is up
+    fn to_pixel(&self, grid: (i32, i32), x: usize, y: usize) -> (usize, usize) {
+        let px = (grid.0 - self.min.0) as usize * CELL_PIXELS + x;
+        let py = (self.max.1 - grid.1) as usize * CELL_PIXELS + (CELL_PIXELS - y);
+        (px, py)
+    }
+}
+
+/// Decodes the vertex heights of a landscape into absolute heights
+/// Every row starts relative to the row before, every vertex relative to the one before
+pub fn decode_heights(vertex_heights: &VertexHeights) -> Heights {
+    let mut heights: Heights = Box::new([[0; VERTICES]; VERTICES]);
+    let mut row_offset = vertex_heights.offset as i32;
+    for y in 0..VERTICES {
+        row_offset += vertex_heights.data[y][0] as i32;
+        let mut height = row_offset;
+        for x in 0..VERTICES {
+            if x > 0 {
+                height += vertex_heights.data[y][x] as i32;
+            }
+            heights[y][x] = height;
+        }
+    }
+    heights
+}
+
+/// Encodes absolute heights into vertex heights
+/// Fails if two neighbouring vertices are too far apart to be stored
+pub fn encode_heights(heights: &Heights) -> Result<VertexHeights, String> {
+    let mut vertex_heights = VertexHeights {
+        offset: heights[0][0] as f32,
+        ..Default::default()
+    };
+    for y in 0..VERTICES {
+        for x in 0..VERTICES {
+            let delta = match (x, y) {
+                (0, 0) => 0,
+                (0, _) => heights[y][0] - heights[y - 1][0],
+                _ => heights[y][x] - heights[y][x - 1],
+            };
+            vertex_heights.data[y][x] = i8::try_from(delta)
+                .map_err(|_| format!("slope too steep at vertex ({}, {})", x, y))?;
+        }
+    }
+    Ok(vertex_heights)
+}
+
+/// Recalculates the vertex normals of a landscape from its heights
+fn calculate_normals(landscape: &mut Landscape, heights: &Heights) {
+    let height_at = |x: i32, y: i32| -> f32 {
+        let x = x.clamp(0, VERTICES as i32 - 1) as usize;
+        let y = y.clamp(0, VERTICES as i32 - 1) as usize;
+        heights[y][x] as f32 * HEIGHT_SCALE
+    };
+
+    for y in 0..VERTICES as i32 {
+        for x in 0..VERTICES as i32 {
+            let dx = (height_at(x + 1, y) - height_at(x - 1, y)) / (2.0 * VERTEX_SPACING);
+            let dy = (height_at(x, y + 1) - height_at(x, y - 1)) / (2.0 * VERTEX_SPACING);
+            let length = (dx * dx + dy * dy + 1.0).sqrt();
+            landscape.vertex_normals.data[y as usize][x as usize] = [
+                (-dx / length * 127.0) as i8,
+                (-dy / length * 127.0) as i8,
+                (1.0 / length * 127.0) as i8,
+            ];
+        }
+    }
+}
+
+/// Heights are mapped to 16 bit with a fixed offset so that exports can be imported losslessly
+fn height_to_u16(height: i32) -> u16 {
+    (height + 32768).clamp(0, u16::MAX as i32) as u16
+}
+
+fn u16_to_height(value: u16) -> i32 {
+    value as i32 - 32768
+}
+
+/// Returns all landscapes of a plugin by grid, edited records take precedence
+pub fn get_landscapes(data: &PluginMetadata) -> HashMap<(i32, i32), (String, Landscape)> {
+    let mut landscapes = HashMap::default();
+    for (id, record) in data.records.iter().chain(data.edited_records.iter()) {
+        if let TES3Object::Landscape(landscape) = record {
+            landscapes.insert(landscape.grid, (id.clone(), landscape.clone()));
+        }
+    }
+    landscapes
+}
+
+/// Returns the smallest range containing all landscapes of a plugin
+pub fn get_landscape_bounds(data: &PluginMetadata) -> Option<HeightmapInfo> {
+    let landscapes = get_landscapes(data);
+    let mut grids = landscapes.keys();
+    let first = grids.next()?;
+    let mut info = HeightmapInfo {
+        min: *first,
+        max: *first,
+    };
+    for grid in grids {
+        info.min = (info.min.0.min(grid.0), info.min.1.min(grid.1));
+        info.max = (info.max.0.max(grid.0), info.max.1.max(grid.1));
+    }
+    Some(info)
+}
+
+/// Exports the landscape heights of the cells in the given range
+/// Writes the heightmap and a yaml sidecar with the cell range next to it
+pub fn export_heightmap<P>(
+    data: &PluginMetadata,
+    info: &HeightmapInfo,
+    format: EHeightmapFormat,
+    path: P,
+) -> io::Result<usize>
+where
+    P: AsRef<Path>,
+{
+    let (width, height) = (info.width(), info.height());
+    let mut pixels = vec![height_to_u16(0); width * height];
+
+    let mut count = 0;
+    for (grid, (_, landscape)) in get_landscapes(data) {
+        if !info.contains(grid) {
+            continue;
+        }
+        let heights = decode_heights(&landscape.vertex_heights);
+        for (y, row) in heights.iter().enumerate() {
+            for (x, h) in row.iter().enumerate() {
+                let (px, py) = info.to_pixel(grid, x, y);
+                pixels[py * width + px] = height_to_u16(*h);
+            }
+        }
+        count += 1;
+    }
+
+    let path = path.as_ref();
+    match format {
+        EHeightmapFormat::Png => {
+            let writer = BufWriter::new(File::create(path)?);
+            let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Sixteen);
+            let mut writer = encoder.write_header().map_err(to_io_error)?;
+            // png is big endian
+            let bytes = pixels
+                .iter()
+                .flat_map(|p| p.to_be_bytes())
+                .collect::<Vec<_>>();
+            writer.write_image_data(&bytes).map_err(to_io_error)?;
+        }
+        EHeightmapFormat::Raw => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            for p in pixels {
+                writer.write_all(&p.to_le_bytes())?;
+            }
+        }
+    }
+
+    let sidecar = serde_yaml::to_string(info).map_err(to_io_error)?;
+    std::fs::write(path.with_extension("yaml"), sidecar)?;
+
+    Ok(count)
+}
+
+/// Imports a heightmap exported with [`export_heightmap`] into the landscapes of a plugin
+/// Only existing landscapes are changed. Edges shared with landscapes outside of the heightmap
+/// keep the heights of the neighbour so that no seams appear.
+/// Returns the number of changed landscapes
+pub fn import_heightmap<P>(data: &mut PluginMetadata, path: P) -> io::Result<usize>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let sidecar = std::fs::read_to_string(path.with_extension("yaml"))?;
+    let info: HeightmapInfo = serde_yaml::from_str(&sidecar).map_err(to_io_error)?;
+    let (width, height) = (info.width(), info.height());
+
+    let pixels: Vec<u16> = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("png") => {
+            let decoder = png::Decoder::new(File::open(path)?);
+            let mut reader = decoder.read_info().map_err(to_io_error)?;
+            let mut buffer = vec![0; reader.output_buffer_size()];
+            let frame = reader.next_frame(&mut buffer).map_err(to_io_error)?;
+            if frame.color_type != png::ColorType::Grayscale
+                || frame.bit_depth != png::BitDepth::Sixteen
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Heightmap must be a 16-bit grayscale png",
+                ));
+            }
+            buffer[..frame.buffer_size()]
+                .chunks_exact(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .collect()
+        }
+        _ => {
+            let mut bytes = vec![];
+            File::open(path)?.read_to_end(&mut bytes)?;
+            bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .collect()
+        }
+    };
+    if pixels.len() != width * height {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Heightmap size does not match its cell range",
+        ));
+    }
+
+    let landscapes = get_landscapes(data);
+    let mut errors = vec![];
+    let mut updates = vec![];
+    for (grid, (id, landscape)) in landscapes.iter() {
+        if !info.contains(*grid) {
+            continue;
+        }
+
+        let mut heights: Heights = Box::new([[0; VERTICES]; VERTICES]);
+        for (y, row) in heights.iter_mut().enumerate() {
+            for (x, h) in row.iter_mut().enumerate() {
+                let (px, py) = info.to_pixel(*grid, x, y);
+                *h = u16_to_height(pixels[py * width + px]);
+            }
+        }
+
+        // keep the edges of neighbours that are not part of the heightmap
+        let last = VERTICES - 1;
+        let neighbour = |dx: i32, dy: i32| {
+            let neighbour_grid = (grid.0 + dx, grid.1 + dy);
+            if info.contains(neighbour_grid) {
+                return None;
+            }
+            landscapes
+                .get(&neighbour_grid)
+                .map(|(_, l)| decode_heights(&l.vertex_heights))
+        };
+        if let Some(west) = neighbour(-1, 0) {
+            (0..VERTICES).for_each(|y| heights[y][0] = west[y][last]);
+        }
+        if let Some(east) = neighbour(1, 0) {
+            (0..VERTICES).for_each(|y| heights[y][last] = east[y][0]);
+        }
+        if let Some(south) = neighbour(0, -1) {
+            (0..VERTICES).for_each(|x| heights[0][x] = south[last][x]);
+        }
+        if let Some(north) = neighbour(0, 1) {
+            (0..VERTICES).for_each(|x| heights[last][x] = north[0][x]);
+        }
+
+        match encode_heights(&heights) {
+            Ok(vertex_heights) => {
+                let mut landscape = landscape.clone();
+                landscape.vertex_heights = vertex_heights;
+                landscape
+                    .landscape_flags
+                    .insert(LandscapeFlags::USES_VERTEX_HEIGHTS_AND_NORMALS);
+                calculate_normals(&mut landscape, &heights);
+
+                updates.push((id.clone(), TES3Object::from(landscape)));
+            }
+            Err(e) => errors.push(format!("({}, {}): {}", grid.0, grid.1, e)),
+        }
+    }
+
+    // nothing is changed if any cell fails
+    if !errors.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, errors.join("\n")));
+    }
+
+    let mut count = 0;
+    for (id, record) in updates {
+        if data.records.get(&id) != Some(&record) {
+            data.edited_records.insert(id, record);
+            count += 1;
+        }
+    }
+    data.clear_cache();
+    Ok(count)
+}
+
+fn to_io_error<E>(e: E) -> Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    Error::other(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A landscape with the same height at every vertex
+    fn flat_landscape(grid: (i32, i32), height: i32) -> Landscape {
+        let heights: Heights = Box::new([[height; VERTICES]; VERTICES]);
+        Landscape {
+            grid,
+            vertex_heights: encode_heights(&heights).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn decode_encode_round_trip() {
+        let mut vertex_heights = VertexHeights {
+            offset: -120.0,
+            ..Default::default()
+        };
+        for y in 0..VERTICES {
+            for x in 0..VERTICES {
+                vertex_heights.data[y][x] = ((x * 7 + y * 3) % 41) as i8 - 20;
+            }
+        }
+        vertex_heights.data[0][0] = 0;
+
+        let heights = decode_heights(&vertex_heights);
+        assert_eq!(heights[0][0], -120);
+        assert_eq!(heights[0][1], -120 + vertex_heights.data[0][1] as i32);
+        assert_eq!(heights[1][0], -120 + vertex_heights.data[1][0] as i32);
+        assert_eq!(encode_heights(&heights).unwrap(), vertex_heights);
+
+        // the first delta is folded into the offset
+        vertex_heights.data[0][0] = 5;
+        let heights = decode_heights(&vertex_heights);
+        let encoded = encode_heights(&heights).unwrap();
+        assert_eq!(encoded.offset, -115.0);
+        assert_eq!(decode_heights(&encoded), heights);
+    }
+
+    #[test]
+    fn encode_steep_slope() {
+        let mut heights: Heights = Box::new([[0; VERTICES]; VERTICES]);
+        heights[3][10] = 200;
+        assert!(encode_heights(&heights).is_err());
+    }
+
+    #[test]
+    fn import_keeps_neighbour_edges() {
+        let mut data = PluginMetadata::new("test.esp".into(), None);
+        for (grid, height) in [((0, 0), 50), ((1, 0), 90), ((0, 1), 80)] {
+            let record = TES3Object::from(flat_landscape(grid, height));
+            data.records.insert(crate::get_unique_id(&record), record);
+        }
+
+        // a heightmap of only the first cell that raises it to 100
+        let info = HeightmapInfo {
+            min: (0, 0),
+            max: (0, 0),
+        };
+        let path = std::env::temp_dir().join("tes3edit_import_keeps_neighbour_edges.raw");
+        let bytes = (0..info.width() * info.height())
+            .flat_map(|_| height_to_u16(100).to_le_bytes())
+            .collect::<Vec<_>>();
+        std::fs::write(&path, bytes).unwrap();
+        std::fs::write(
+            path.with_extension("yaml"),
+            serde_yaml::to_string(&info).unwrap(),
+        )
+        .unwrap();
+
+        let count = import_heightmap(&mut data, &path).unwrap();
+        assert_eq!(count, 1);
+        let landscapes = get_landscapes(&data);
+        let heights = decode_heights(&landscapes[&(0, 0)].1.vertex_heights);
+        let last = VERTICES - 1;
+        for i in 0..last {
+            // the east edge is shared with (1, 0), the north edge with (0, 1)
+            assert_eq!(heights[i][last], 90);
+            assert_eq!(heights[last][i], 80);
+        }
+        // the corner of both edges is set by the north neighbour
+        assert_eq!(heights[last][last], 80);
+        // the rest comes from the heightmap
+        assert_eq!(heights[0][0], 100);
+        assert_eq!(heights[last - 1][last - 1], 100);
+        // the neighbours are unchanged
+        assert_eq!(
+            decode_heights(&landscapes[&(1, 0)].1.vertex_heights)[0][0],
+            90
+        );
+    }
+}
@@ -10,6 +10,26 @@ use std::{
 
 mod app;
 mod app_ui;
+mod chains;
+mod clean;
+mod conflicts;
+mod dialogue;
+mod filters;
+mod gmst;
+mod heightmap;
+mod loader;
+mod merge;
+mod pathgrid;
+mod query;
+mod quests;
+mod script;
+mod script_data;
+mod search;
+#[cfg(not(target_arch = "wasm32"))]
+mod sqlite;
+mod table;
+#[cfg(test)]
+mod test_utils;
 mod views;
 
 pub use app::TemplateApp;
@@ -17,7 +37,7 @@ pub use app::TemplateApp;
 use egui_notify::Toasts;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter};
-use tes3::esp::{EditorId, Plugin, TES3Object, TypeInfo};
+use tes3::esp::{editor::Editor, EditorId, Plugin, Reference, TES3Object, TypeInfo};
 
 pub struct EditData {
     pub current_plugin_id: String,
@@ -25,6 +45,11 @@ pub struct EditData {
     pub search_text: String,
     pub record_type: ERecordType,
     pub copied_record: Option<TES3Object>,
+
+    pub editor_tab: EEditorTab,
+    pub layout: LayoutData,
+    pub script: ScriptEditorData,
+    pub gmst_defaults: gmst::GmstDefaults,
 }
 
 impl Default for EditData {
@@ -35,16 +60,79 @@ impl Default for EditData {
             search_text: Default::default(),
             record_type: ERecordType::MISC,
             copied_record: None,
+            editor_tab: EEditorTab::default(),
+            layout: LayoutData::default(),
+            script: ScriptEditorData::default(),
+            gmst_defaults: gmst::GmstDefaults::default(),
+        }
+    }
+}
+
+/// State of the top-down cell layout
+pub struct LayoutData {
+    /// the record the layout was set up for
+    pub record_id: String,
+    pub zoom: f32,
+    pub offset: egui::Vec2,
+    pub selected_reference: Option<(u32, u32)>,
+    pub scroll_to_selected: bool,
+    pub selected_point: Option<usize>,
+    /// the bounds of the view while something is dragged, so that it does not move along
+    pub drag_bounds: Option<egui::Rect>,
+    /// the cell of the selected pathgrid
+    pub pathgrid_cell: Option<tes3::esp::Cell>,
+    /// record tags of all base objects of the open plugins by lowercase id
+    pub base_types: HashMap<String, String>,
+}
+impl Default for LayoutData {
+    fn default() -> Self {
+        Self {
+            record_id: Default::default(),
+            zoom: 1.0,
+            offset: egui::Vec2::ZERO,
+            selected_reference: None,
+            scroll_to_selected: false,
+            selected_point: None,
+            drag_bounds: None,
+            pathgrid_cell: None,
+            base_types: Default::default(),
         }
     }
 }
 
+/// State of the script editor
+#[derive(Default)]
+pub struct ScriptEditorData {
+    /// char index of the text cursor
+    pub cursor: Option<usize>,
+    /// errors of the current text, None if the text changed
+    pub errors: Option<Vec<script::ScriptError>>,
+    /// ids of all global variables of the open plugins
+    pub globals: Vec<String>,
+    /// compiled data and mismatches with the hash of the script they were made for
+    pub compiled_data: Option<(u64, String, Vec<script_data::ScriptMismatch>)>,
+}
+
 pub struct RecordsData {
     pub search_text: String,
     pub record_type: ERecordType,
 
     pub records: HashMap<String, HashMap<String, Vec<String>>>,
     pub cache: HashMap<String, Vec<String>>,
+
+    /// the parsed plugins in load order
+    pub plugins: Vec<(String, Plugin)>,
+    /// the file of every plugin in plugins by name
+    pub paths: HashMap<String, PathBuf>,
+    /// results of the last INFO chain check
+    pub chain_issues: Option<Vec<chains::ChainIssue>>,
+
+    /// unique id of the record in the history panel
+    pub selected_id: Option<String>,
+    pub history: Option<UiData>,
+
+    /// parses the plugins in the background
+    pub loader: Option<loader::PluginLoader>,
 }
 impl Default for RecordsData {
     fn default() -> Self {
@@ -53,23 +141,161 @@ impl Default for RecordsData {
             record_type: ERecordType::MISC,
             records: Default::default(),
             cache: Default::default(),
+            plugins: Default::default(),
+            paths: Default::default(),
+            chain_issues: None,
+            selected_id: None,
+            history: None,
+            loader: None,
+        }
+    }
+}
+
+pub struct MapData {
+    pub mode: EMapMode,
+    pub zoom: f32,
+    pub offset: egui::Vec2,
+    pub selected: Option<(i32, i32)>,
+
+    /// exterior cells of all open plugins by grid
+    pub cells: HashMap<(i32, i32), MapCell>,
+}
+impl Default for MapData {
+    fn default() -> Self {
+        Self {
+            mode: EMapMode::default(),
+            zoom: 1.0,
+            offset: egui::Vec2::ZERO,
+            selected: None,
+            cells: Default::default(),
+        }
+    }
+}
+
+/// An exterior cell on the map
+#[derive(Default)]
+pub struct MapCell {
+    /// unique id of the cell record
+    pub id: String,
+    pub name: String,
+    pub region: Option<String>,
+    /// ids of the plugins touching this cell, in the order they were opened
+    pub plugins: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct DialogueViewData {
+    pub search_text: String,
+    /// unique id of the selected DIAL
+    pub selected_topic: Option<String>,
+    /// unique id of the selected INFO
+    pub selected_info: Option<String>,
+}
+
+/// State of the spreadsheet table view
+pub struct TableData {
+    pub record_type: ERecordType,
+    pub filter: String,
+    pub sort_column: Option<usize>,
+    pub sort_ascending: bool,
+
+    /// field paths of the columns
+    pub columns: Vec<String>,
+    pub rows: Vec<table::TableRow>,
+    /// indices of the filtered and sorted rows, None when the filter, sort or rows changed
+    pub visible: Option<Vec<usize>>,
+    /// the plugin and record type the rows were created for
+    pub source: Option<(String, ERecordType)>,
+    /// a read csv waiting for confirmation
+    pub csv_import: Option<table::CsvImport>,
+}
+impl Default for TableData {
+    fn default() -> Self {
+        Self {
+            record_type: ERecordType::MISC,
+            filter: Default::default(),
+            sort_column: None,
+            sort_ascending: true,
+            columns: Default::default(),
+            rows: Default::default(),
+            visible: None,
+            source: None,
+            csv_import: None,
         }
     }
 }
 
+/// Records a query runs over
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum EQuerySource {
+    #[default]
+    CurrentPlugin,
+    LoadOrder,
+}
+
+#[derive(Default)]
+pub struct QueryData {
+    pub open: bool,
+    pub text: String,
+    pub source: EQuerySource,
+
+    pub results: Vec<query::QueryResult>,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct TextSearchData {
+    pub open: bool,
+    pub query: String,
+    pub use_regex: bool,
+    pub whole_word: bool,
+    pub case_sensitive: bool,
+
+    pub results: Vec<search::TextMatch>,
+    /// the last invalid regex
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct QuestData {
+    pub search_text: String,
+    /// id of the selected journal
+    pub selected: Option<String>,
+
+    /// journals of all open plugins
+    pub quests: Vec<quests::Quest>,
+    /// journal changes of the selected quest
+    pub calls: Vec<quests::JournalCall>,
+}
+
+#[derive(Default)]
+pub struct HeightmapData {
+    /// export all landscapes of the plugin
+    pub whole_worldspace: bool,
+    /// the south-west cell
+    pub min: (i32, i32),
+    /// the north-east cell
+    pub max: (i32, i32),
+    pub format: heightmap::EHeightmapFormat,
+}
+
 #[derive(Default)]
 pub struct CompareData {
     pub path: PathBuf,
     pub plugins: Vec<CompareItemViewModel>,
 
-    // these must be in sync
-    pub map: HashMap<String, Vec<u64>>,
-    pub conflicting_ids: Vec<String>,
+    /// the plugins of every conflicting record
+    pub conflicts: conflicts::ConflictIndex,
+    /// the plugins of the first load are indexed at once when it finished, later ones one by one
+    pub conflicts_built: bool,
 
     pub selected_id: String,
 
     pub ui_data_id: String,
     pub ui_data: Option<UiData>,
+
+    /// parses the enabled plugins in the background
+    pub loader: Option<loader::PluginLoader>,
 }
 
 #[derive(Default)]
@@ -95,10 +321,56 @@ pub struct CompareItemViewModel {
     pub plugin: Plugin,
     /// A list of all records by unique id of that plugin
     pub records: Vec<String>,
+    /// An open plugin of edit mode with its unsaved changes
+    pub in_memory: bool,
 }
+impl CompareData {
+    /// Indexes all enabled plugins at once in parallel
+    pub fn build_conflicts(&mut self) {
+        let plugins = self
+            .plugins
+            .iter()
+            .filter(|p| p.enabled)
+            .map(|p| (p.id, p.records.as_slice()))
+            .collect::<Vec<_>>();
+        self.conflicts = conflicts::ConflictIndex::from_plugins(&plugins);
+        self.conflicts_built = true;
+        self.ui_data = None;
+    }
+
+    /// Indexes a loaded plugin at its place in the list
+    pub fn index_plugin(&mut self, index: usize) {
+        let position = self.plugins[..index]
+            .iter()
+            .filter(|p| self.conflicts.contains_plugin(p.id))
+            .count();
+        let vm = &self.plugins[index];
+        self.conflicts.add_plugin(vm.id, &vm.records, position);
+        self.ui_data = None;
+    }
+
+    /// Removes a plugin from the conflicts
+    pub fn unindex_plugin(&mut self, index: usize) {
+        self.conflicts.remove_plugin(self.plugins[index].id);
+        self.ui_data = None;
+    }
+
+    /// Updates the conflicts after the list of plugins was reordered
+    pub fn update_conflict_order(&mut self) {
+        let order = self.plugins.iter().map(|p| p.id).collect();
+        self.conflicts.set_order(order);
+        self.ui_data = None;
+    }
+}
+
 impl CompareItemViewModel {
     pub fn get_name(&self) -> String {
-        self.path.file_name().unwrap().to_string_lossy().to_string()
+        let name = self.path.file_name().unwrap().to_string_lossy().to_string();
+        if self.in_memory {
+            format!("{} (open)", name)
+        } else {
+            name
+        }
     }
     pub fn get_extension(&self) -> String {
         self.path.extension().unwrap().to_string_lossy().to_string()
@@ -120,44 +392,6 @@ pub fn get_path_hash(e: &std::path::PathBuf) -> u64 {
     std::hash::Hasher::finish(&hasher)
 }
 
-/// Slow conflict lookup between plugins
-pub fn generate_conflict_map(data: &CompareData) -> std::collections::HashMap<String, Vec<u64>> {
-    let mut conflict_map: HashMap<String, Vec<u64>> = HashMap::default();
-
-    for plugin in data.plugins.iter().filter(|e| e.enabled) {
-        let mut new_records: Vec<String> = vec![];
-        for record_id in plugin.records.iter() {
-            if conflict_map.contains_key(record_id) {
-                //conflict_map[record_id].push(plugin.id);
-                // update map
-                let mut v = conflict_map[record_id].clone();
-                v.push(plugin.id);
-                conflict_map.insert(record_id.clone(), v);
-            } else {
-                new_records.push(record_id.clone());
-            }
-        }
-
-        for id in new_records {
-            conflict_map.insert(id, vec![plugin.id]);
-        }
-    }
-
-    // ??? rust pls
-    let mut map: HashMap<String, Vec<u64>> = HashMap::default();
-    let filtered = conflict_map.iter().filter(|p| p.1.len() > 1);
-    for (k, v) in filtered {
-        map.insert(k.to_owned(), v.clone());
-    }
-
-    // check for actual conflicts
-    // for kvp in map {
-
-    // }
-
-    map
-}
-
 /// App States
 #[derive(Default, PartialEq, Debug)]
 pub enum EAppState {
@@ -165,6 +399,10 @@ pub enum EAppState {
     SingleEdit,
     Records,
     Compare,
+    Map,
+    Dialogue,
+    Quests,
+    Table,
 }
 
 /// Modal windows
@@ -174,6 +412,28 @@ pub enum EModalState {
     None,
     ModalCompareInit,
     Settings,
+    Heightmap,
+}
+
+/// Record editor tabs
+#[derive(Default, PartialEq, Debug, Clone, Copy)]
+pub enum EEditorTab {
+    #[default]
+    Fields,
+    Layout,
+    Pathgrid,
+    Filters,
+    Script,
+    CompiledData,
+}
+
+/// Map color modes
+#[derive(Default, EnumIter, Debug, Clone, Copy, PartialEq, Display)]
+pub enum EMapMode {
+    #[default]
+    Regions,
+    Plugins,
+    Conflicts,
 }
 
 /// App scale
@@ -315,6 +575,95 @@ pub fn get_unique_id(record: &TES3Object) -> String {
     format!("{},{}", record.tag_str(), record.editor_id())
 }
 
+/// Flattens the fields of an editor into dotted names and json values
+pub fn get_fields_recursive(
+    field: &mut dyn Editor,
+    fields: &mut Vec<(String, String)>,
+    field_name: String,
+) {
+    if let Some(sub) = field.get_editor_list() {
+        // if that field is atype that has itself fields
+        //we need to recursively get them
+        for (field_name2, field2) in sub {
+            let complex_name = format!("{}.{}", field_name, field_name2);
+            get_fields_recursive(field2, fields, complex_name);
+        }
+    } else {
+        fields.push((field_name, field.to_json()));
+    }
+}
+
+/// A record as json, the references of cells are written as a list sorted by their key
+/// because json keys must be strings
+pub fn record_to_json(record: &TES3Object) -> Result<serde_json::Value, String> {
+    let TES3Object::Cell(cell) = record else {
+        return serde_json::to_value(record).map_err(|e| e.to_string());
+    };
+    let mut cell = cell.clone();
+    let mut references = std::mem::take(&mut cell.references)
+        .into_values()
+        .collect::<Vec<_>>();
+    references.sort_by_key(|r| (r.mast_index, r.refr_index));
+
+    let mut json = serde_json::to_value(TES3Object::Cell(cell)).map_err(|e| e.to_string())?;
+    json["references"] = serde_json::to_value(references).map_err(|e| e.to_string())?;
+    Ok(json)
+}
+
+/// A record from json written by [`record_to_json`]
+pub fn record_from_json(mut json: serde_json::Value) -> Result<TES3Object, String> {
+    let references = match json.get_mut("references") {
+        Some(list @ serde_json::Value::Array(_)) => {
+            Some(std::mem::replace(list, serde_json::json!({})))
+        }
+        _ => None,
+    };
+    let mut record = serde_json::from_value(json).map_err(|e| e.to_string())?;
+    if let (TES3Object::Cell(cell), Some(references)) = (&mut record, references) {
+        let references: Vec<Reference> =
+            serde_json::from_value(references).map_err(|e| e.to_string())?;
+        cell.references = references
+            .into_iter()
+            .map(|r| ((r.mast_index, r.refr_index), r))
+            .collect();
+    }
+    Ok(record)
+}
+
+/// Sets a field of a record by its dotted path with a json round-trip
+pub fn set_field_value(
+    record: &TES3Object,
+    path: &str,
+    value: serde_json::Value,
+) -> Result<TES3Object, String> {
+    let mut json = record_to_json(record)?;
+    let mut field = &mut json;
+    for part in path.split('.') {
+        field = match field {
+            serde_json::Value::Object(map) => map.get_mut(part),
+            serde_json::Value::Array(list) => {
+                part.parse::<usize>().ok().and_then(|i| list.get_mut(i))
+            }
+            _ => None,
+        }
+        .ok_or_else(|| format!("unknown field {}", path))?;
+    }
+    *field = value;
+    record_from_json(json)
+}
+
+/// All fields of a record as dotted names and json values
+pub fn get_record_fields(record: &TES3Object) -> Vec<(String, String)> {
+    let mut record = record.clone();
+    let mut fields = vec![];
+    if let Some(record_fields) = record.get_editor_list() {
+        for (field_name, field) in record_fields {
+            get_fields_recursive(field, &mut fields, field_name.to_owned());
+        }
+    }
+    fields
+}
+
 /// Creates an id for a plugin
 ///
 /// # Panics
@@ -406,8 +755,12 @@ pub struct PluginMetadata {
     pub records: HashMap<String, TES3Object>,
     /// cached ids of all records and edited records of this plugin
     pub cached_ids: HashMap<String, Vec<String>>,
+    /// cached unique ids of the evil GMSTs of this plugin
+    pub cached_evil_gmsts: Vec<String>,
     pub edited_records: HashMap<String, TES3Object>,
     pub selected_record_id: Option<String>,
+    /// the topic of every INFO by unique ids
+    pub topics: HashMap<String, String>,
 }
 
 impl PluginMetadata {
@@ -417,13 +770,16 @@ impl PluginMetadata {
             full_path,
             records: HashMap::default(),
             cached_ids: HashMap::default(),
+            cached_evil_gmsts: Vec::default(),
             edited_records: HashMap::default(),
             selected_record_id: None,
+            topics: HashMap::default(),
         }
     }
 
     pub fn clear_cache(&mut self) {
         self.cached_ids.clear();
+        self.cached_evil_gmsts.clear();
     }
 
     /// Regenerates record id cache of this plugin
@@ -463,6 +819,46 @@ impl PluginMetadata {
         }
 
         self.cached_ids = grouped;
+        self.cached_evil_gmsts = gmst::get_evil_gmsts(self);
+    }
+
+    /// Selects a record for editing
+    /// Unchanged edited records are cleaned up and a copy of the selected record is added to the edited records
+    pub fn select_record(&mut self, id: &str) {
+        // cleanup old records
+        let mut to_remove = Vec::new();
+        for (key, edited_record) in self.edited_records.iter() {
+            if let Some(original) = self.records.get(key) {
+                // remove if no change
+                if original.eq(edited_record) {
+                    to_remove.push(key.clone());
+                }
+            }
+        }
+        for r in to_remove {
+            self.edited_records.remove(&r);
+        }
+
+        // add a copy of this record to the edited records
+        if !self.edited_records.contains_key(id) {
+            if let Some(record) = self.records.get(id) {
+                self.edited_records.insert(id.to_owned(), record.clone());
+            }
+        }
+
+        self.selected_record_id = Some(id.to_string());
+    }
+
+    /// Keeps the dialogue topics in sync when the unique id of a record changes
+    pub fn rename_record(&mut self, old_id: &str, new_id: &str) {
+        if let Some(topic) = self.topics.remove(old_id) {
+            self.topics.insert(new_id.to_owned(), topic);
+        }
+        for topic in self.topics.values_mut() {
+            if topic == old_id {
+                *topic = new_id.to_owned();
+            }
+        }
     }
 
     /// Returns the get records of this [`PluginMetadata`].
@@ -493,12 +889,28 @@ impl PluginMetadata {
         let mut records: Vec<_> = final_records.values().cloned().collect();
         let pos = records.iter().position(|e| e.tag_str() == "TES3").unwrap();
         let header = records.remove(pos);
+
+        // topics are followed by their INFOs
+        let mut records = dialogue::sort_dialogue(records, &self.topics);
         records.insert(0, header);
 
         records
     }
 }
 
+/// Returns false and reports the INFOs that have no topic, they would be saved into the wrong topic
+pub(crate) fn check_info_topics(data: &PluginMetadata, toasts: &mut Toasts) -> bool {
+    let ids = dialogue::get_infos_without_topic(data);
+    if ids.is_empty() {
+        return true;
+    }
+    toasts.error(format!(
+        "Not saved, INFOs without a topic: {}",
+        ids.join(", ")
+    ));
+    false
+}
+
 /// Saves records as plugin to the specified path
 /// If overwrite is not specified, appends new.esp as extension
 pub fn save_plugin<P>(
@@ -510,9 +922,13 @@ pub fn save_plugin<P>(
 where
     P: AsRef<Path>,
 {
+    if !check_info_topics(data, toasts) {
+        return false;
+    }
     let mut plugin = Plugin {
         objects: data.get_records_sorted(),
     };
+    script_data::check_edited_scripts(data, &mut plugin.objects, toasts);
     // save
     let mut output_path = plugin_path.as_ref().to_path_buf();
     if !overwrite {
@@ -540,7 +956,27 @@ pub fn save_patch<P>(data: &PluginMetadata, plugin_path: P, toasts: &mut Toasts)
 where
     P: AsRef<Path>,
 {
+    if !check_info_topics(data, toasts) {
+        return false;
+    }
     let mut records_vec: Vec<_> = data.edited_records.values().cloned().collect();
+    script_data::check_edited_scripts(data, &mut records_vec, toasts);
+
+    // INFOs need their topic
+    let mut topic_ids = data
+        .edited_records
+        .keys()
+        .filter_map(|id| data.topics.get(id))
+        .filter(|topic_id| !data.edited_records.contains_key(*topic_id))
+        .collect::<Vec<_>>();
+    topic_ids.sort();
+    topic_ids.dedup();
+    for topic_id in topic_ids {
+        if let Some(topic) = data.records.get(topic_id) {
+            records_vec.push(topic.clone());
+        }
+    }
+    let mut records_vec = dialogue::sort_dialogue(records_vec, &data.topics);
 
     // if a header in changed files, then take that one instead of the original one
     // TODO panic here if no header since this is undefined behavior
@@ -652,3 +1088,77 @@ fn parse_plugin(path: &PathBuf) -> io::Result<Plugin> {
 
     Ok(plugin)
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tes3::esp::{Cell, Npc, NpcData, NpcStats, Weapon};
+
+    use super::*;
+    use crate::test_utils::{cell, reference};
+
+    #[test]
+    fn set_weapon_field() {
+        let weapon = TES3Object::Weapon(Weapon {
+            id: "iron dagger".into(),
+            ..Default::default()
+        });
+
+        let edited = set_field_value(&weapon, "data.chop_max", json!(12)).unwrap();
+        let TES3Object::Weapon(edited) = edited else {
+            panic!("not a weapon");
+        };
+        assert_eq!(edited.id, "iron dagger");
+        assert_eq!(edited.data.chop_max, 12);
+
+        assert!(set_field_value(&weapon, "data.chop_max", json!(-1)).is_err());
+        assert!(set_field_value(&weapon, "data.damage", json!(1)).is_err());
+    }
+
+    #[test]
+    fn set_npc_field() {
+        let npc = TES3Object::Npc(Npc {
+            id: "fargoth".into(),
+            data: NpcData {
+                stats: Some(NpcStats::default()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let edited = set_field_value(&npc, "data.level", json!(5)).unwrap();
+        let edited = set_field_value(&edited, "data.stats.skills.3", json!(40)).unwrap();
+        let TES3Object::Npc(edited) = edited else {
+            panic!("not an npc");
+        };
+        assert_eq!(edited.data.level, 5);
+        assert_eq!(edited.data.stats.unwrap().skills[3], 40);
+
+        assert!(set_field_value(&npc, "data.stats.skills.27", json!(1)).is_err());
+    }
+
+    #[test]
+    fn set_cell_field() {
+        let cell = TES3Object::Cell(Cell {
+            name: "Balmora".into(),
+            ..cell(vec![
+                reference(0, 1, "lamp"),
+                reference(0, 2, "rug"),
+                reference(1, 5, "table"),
+            ])
+        });
+
+        // references are listed by their key
+        let edited = set_field_value(&cell, "references.1.id", json!("chair")).unwrap();
+        let edited = set_field_value(&edited, "data.grid", json!([-3, -2])).unwrap();
+        let TES3Object::Cell(edited) = edited else {
+            panic!("not a cell");
+        };
+        assert_eq!(edited.name, "Balmora");
+        assert_eq!(edited.data.grid, (-3, -2));
+        assert_eq!(edited.references.len(), 3);
+        assert_eq!(edited.references[&(0, 1)].id, "lamp");
+        assert_eq!(edited.references[&(0, 2)].id, "chair");
+        assert_eq!(edited.references[&(1, 5)].id, "table");
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+};
+
+use tes3::esp::Plugin;
+
+use crate::{get_unique_id, parse_plugin};
+
+/// State of a file in the loader
+#[derive(Debug, Clone, PartialEq)]
+pub enum ELoadStatus {
+    Pending,
+    Loading,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+impl Display for ELoadStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ELoadStatus::Pending => write!(f, "pending"),
+            ELoadStatus::Loading => write!(f, "loading"),
+            ELoadStatus::Done => write!(f, "done"),
+            ELoadStatus::Failed(error) => write!(f, "failed: {}", error),
+            ELoadStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// A parsed plugin and the unique ids of its records, in plugin order
+pub struct LoadedPlugin {
+    pub plugin: Plugin,
+    pub records: Vec<String>,
+}
+
+/// Messages of the workers, by index of the file
+enum ELoadMessage {
+    Started(usize),
+    Loaded(usize, LoadedPlugin),
+    Failed(usize, String),
+}
+
+/// Parses plugins on background threads
+pub struct PluginLoader {
+    /// the files in load order and their state
+    pub files: Vec<(PathBuf, ELoadStatus)>,
+    sender: Sender<ELoadMessage>,
+    receiver: Receiver<ELoadMessage>,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Parses and indexes one plugin on the thread pool
+fn load_plugin(index: usize, path: PathBuf, cancelled: &AtomicBool, sender: &Sender<ELoadMessage>) {
+    if cancelled.load(Ordering::Relaxed) {
+        return;
+    }
+    // the loader was dropped
+    if sender.send(ELoadMessage::Started(index)).is_err() {
+        return;
+    }
+    let message = match parse_plugin(&path) {
+        Ok(plugin) => {
+            let records = plugin.objects.iter().map(get_unique_id).collect();
+            ELoadMessage::Loaded(index, LoadedPlugin { plugin, records })
+        }
+        Err(error) => ELoadMessage::Failed(index, error.to_string()),
+    };
+    let _ = sender.send(message);
+}
+
+impl PluginLoader {
+    /// Starts parsing the files on the rayon thread pool
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let mut loader = Self {
+            files: vec![],
+            sender,
+            receiver,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        for path in paths {
+            loader.add(path);
+        }
+        loader
+    }
+
+    /// Queues another file
+    pub fn add(&mut self, path: PathBuf) {
+        let index = self.files.len();
+        self.files.push((path.clone(), ELoadStatus::Pending));
+
+        let cancelled = self.cancelled.clone();
+        let sender = self.sender.clone();
+        rayon::spawn_fifo(move || load_plugin(index, path, &cancelled, &sender));
+    }
+
+    /// Updates the states and returns the plugins that finished since the last call
+    pub fn poll(&mut self) -> Vec<(usize, LoadedPlugin)> {
+        let mut loaded = vec![];
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                ELoadMessage::Started(index) => self.files[index].1 = ELoadStatus::Loading,
+                ELoadMessage::Loaded(index, plugin) => {
+                    self.files[index].1 = ELoadStatus::Done;
+                    loaded.push((index, plugin));
+                }
+                ELoadMessage::Failed(index, error) => {
+                    self.files[index].1 = ELoadStatus::Failed(error);
+                }
+            }
+        }
+
+        // files the workers did not start anymore
+        if self.cancelled.load(Ordering::Relaxed) {
+            for (_, status) in self.files.iter_mut() {
+                if *status == ELoadStatus::Pending {
+                    *status = ELoadStatus::Cancelled;
+                }
+            }
+        }
+        loaded
+    }
+
+    /// Stops the workers, files that are already parsing still finish
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Number of files that are not pending or loading anymore
+    pub fn get_finished_count(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|(_, s)| !matches!(s, ELoadStatus::Pending | ELoadStatus::Loading))
+            .count()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.get_finished_count() == self.files.len()
+    }
+
+    /// File name of a file in the loader
+    pub fn get_name(&self, index: usize) -> String {
+        self.files[index]
+            .0
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
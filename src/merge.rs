@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Serializer};
+use tes3::esp::{Header, Plugin, Reference, TES3Object};
+
+use crate::{
+    chains::merge_topics,
+    clean::{get_master_names, get_reference_origin},
+    dialogue::get_chain_order,
+    get_unique_id, record_to_json,
+};
+
+/// The winning version of a record in the load order
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedRecord {
+    /// the last plugin that has the record
+    pub plugin: String,
+    #[serde(serialize_with = "serialize_record")]
+    pub record: TES3Object,
+}
+
+/// Writes the record with [`record_to_json`], the references of cells have tuple keys
+fn serialize_record<S: Serializer>(record: &TES3Object, serializer: S) -> Result<S::Ok, S::Error> {
+    record_to_json(record)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(serializer)
+}
+
+/// References of a cell by the lowercase name of the plugin that created them and their index
+/// Kept in the order they first appear
+#[derive(Default)]
+struct MergedReferences {
+    references: Vec<Reference>,
+    indices: HashMap<(String, u32), usize>,
+}
+
+/// The effective record of every id, the last plugin in load order wins
+/// CELL references are merged across plugins and renumbered for a plugin without masters
+/// Records are in the order they first appear, INFOs follow their topic in chain order
+pub fn get_merged_records(plugins: &[(String, Plugin)]) -> Vec<MergedRecord> {
+    let mut records: Vec<MergedRecord> = vec![];
+    // ids are case insensitive in the game
+    let mut indices: HashMap<String, usize> = HashMap::new();
+    let mut cell_references: HashMap<usize, MergedReferences> = HashMap::new();
+    for (plugin_name, plugin) in plugins {
+        let master_names = get_master_names(plugin);
+        for record in plugin.objects.iter() {
+            // headers are per plugin and INFOs are merged with their topic
+            if matches!(record, TES3Object::Header(_) | TES3Object::DialogueInfo(_)) {
+                continue;
+            }
+            let merged = MergedRecord {
+                plugin: plugin_name.clone(),
+                record: record.clone(),
+            };
+            let key = get_unique_id(record).to_lowercase();
+            let index = match indices.get(&key) {
+                Some(index) => {
+                    records[*index] = merged;
+                    *index
+                }
+                None => {
+                    indices.insert(key, records.len());
+                    records.push(merged);
+                    records.len() - 1
+                }
+            };
+
+            let TES3Object::Cell(cell) = record else {
+                continue;
+            };
+            let merged_references = cell_references.entry(index).or_default();
+            let mut keys = cell.references.keys().collect::<Vec<_>>();
+            keys.sort();
+            for key in keys {
+                // references of masters missing from the header can't be matched
+                let Some(origin) = get_reference_origin(*key, plugin_name, &master_names) else {
+                    continue;
+                };
+                let reference = cell.references[key].clone();
+                match merged_references.indices.get(&origin) {
+                    Some(i) => merged_references.references[*i] = reference,
+                    None => {
+                        merged_references
+                            .indices
+                            .insert(origin, merged_references.references.len());
+                        merged_references.references.push(reference);
+                    }
+                }
+            }
+        }
+    }
+
+    // the merged plugin has no masters, all references become its own
+    // deleted references only removed references of earlier plugins
+    let mut refr_index = 0;
+    for (index, record) in records.iter_mut().enumerate() {
+        let TES3Object::Cell(cell) = &mut record.record else {
+            continue;
+        };
+        let Some(merged_references) = cell_references.remove(&index) else {
+            continue;
+        };
+        cell.references.clear();
+        for mut reference in merged_references.references {
+            if reference.deleted.is_some() {
+                continue;
+            }
+            refr_index += 1;
+            reference.mast_index = 0;
+            reference.refr_index = refr_index;
+            cell.references.insert((0, refr_index), reference);
+        }
+    }
+
+    let topics = merge_topics(plugins);
+    let mut merged = vec![];
+    for record in records {
+        let TES3Object::Dialogue(dialogue) = &record.record else {
+            merged.push(record);
+            continue;
+        };
+        let Some(topic) = topics.get(&dialogue.id.to_lowercase()) else {
+            merged.push(record);
+            continue;
+        };
+
+        let infos = topic.infos.iter().map(|(info, _)| info).collect::<Vec<_>>();
+        let order = get_chain_order(&infos);
+        merged.push(record);
+        merged.extend(order.into_iter().map(|i| MergedRecord {
+            plugin: topic.infos[i].1.clone(),
+            record: TES3Object::DialogueInfo(topic.infos[i].0.clone()),
+        }));
+    }
+    merged
+}
+
+/// Creates a plugin without masters that contains the merged records
+pub fn create_merged_plugin(records: &[MergedRecord]) -> Plugin {
+    let header = Header {
+        version: 1.3,
+        description: "Merged load order".into(),
+        ..Default::default()
+    };
+    let mut objects = vec![TES3Object::Header(header)];
+    objects.extend(records.iter().map(|r| r.record.clone()));
+    Plugin { objects }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{cell, get_references, reference};
+
+    use super::*;
+
+    fn plugin(masters: &[&str], references: Vec<Reference>) -> Plugin {
+        let header = Header {
+            masters: masters.iter().map(|m| (m.to_string(), 0)).collect(),
+            ..Default::default()
+        };
+        Plugin {
+            objects: vec![
+                TES3Object::Header(header),
+                TES3Object::Cell(cell(references)),
+            ],
+        }
+    }
+
+    fn get_cell_references(records: &[MergedRecord]) -> Vec<((u32, u32), String)> {
+        let TES3Object::Cell(cell) = &records[0].record else {
+            panic!("not a cell");
+        };
+        get_references(&cell.references)
+    }
+
+    #[test]
+    fn cell_references() {
+        let deleted = Reference {
+            deleted: Some(0),
+            ..reference(1, 2, "chair")
+        };
+        let plugins = vec![
+            (
+                "Master.esm".to_string(),
+                plugin(
+                    &[],
+                    vec![reference(0, 1, "table"), reference(0, 2, "chair")],
+                ),
+            ),
+            (
+                "a.esp".to_string(),
+                plugin(
+                    &["master.esm"],
+                    vec![reference(0, 1, "lamp"), reference(1, 1, "table_2"), deleted],
+                ),
+            ),
+            (
+                "b.esp".to_string(),
+                plugin(
+                    &["Master.esm", "a.esp"],
+                    vec![reference(0, 1, "rug"), reference(2, 1, "lamp_2")],
+                ),
+            ),
+        ];
+
+        let records = get_merged_records(&plugins);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].plugin, "b.esp");
+        assert_eq!(
+            get_cell_references(&records),
+            vec![
+                ((0, 1), "table_2".to_string()),
+                ((0, 2), "lamp_2".to_string()),
+                ((0, 3), "rug".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_master_references() {
+        let plugins = vec![(
+            "a.esp".to_string(),
+            plugin(&[], vec![reference(0, 7, "lamp"), reference(1, 1, "table")]),
+        )];
+
+        let records = get_merged_records(&plugins);
+        assert_eq!(
+            get_cell_references(&records),
+            vec![((0, 1), "lamp".to_string())]
+        );
+    }
+
+    #[test]
+    fn serialize_merged_cell() {
+        let plugins = vec![(
+            "a.esp".to_string(),
+            plugin(&[], vec![reference(0, 2, "rug"), reference(0, 1, "lamp")]),
+        )];
+
+        let records = get_merged_records(&plugins);
+        let text = serde_json::to_string_pretty(&records).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let ids = json[0]["record"]["references"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["id"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["lamp", "rug"]);
+    }
+}
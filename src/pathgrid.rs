@@ -0,0 +1,418 @@
+use std::fmt::Display;
+
+use tes3::esp::{Cell, PathGrid, TES3Object};
+
+use crate::{views::cell_layout_view::EXTERIOR_CELL_SIZE, PluginMetadata};
+
+/// A problem found in a pathgrid
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathGridIssue {
+    /// points not connected to the largest group of points
+    Island(Vec<usize>),
+    /// an exterior point outside of its cell
+    OutOfCell(usize),
+    /// a connection to a point that does not exist
+    InvalidConnection(usize, u32),
+    /// a connection that is only stored on one of its points
+    OneWayConnection(usize, usize),
+    /// point and connection counts don't match the arrays
+    CountMismatch,
+}
+
+impl PathGridIssue {
+    /// The point to select for this issue
+    pub fn point(&self) -> Option<usize> {
+        match self {
+            PathGridIssue::Island(points) => points.first().copied(),
+            PathGridIssue::OutOfCell(p)
+            | PathGridIssue::InvalidConnection(p, _)
+            | PathGridIssue::OneWayConnection(p, _) => Some(*p),
+            PathGridIssue::CountMismatch => None,
+        }
+    }
+}
+
+impl Display for PathGridIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathGridIssue::Island(points) => {
+                write!(f, "Disconnected island of {} points", points.len())
+            }
+            PathGridIssue::OutOfCell(p) => write!(f, "Point {} is outside of the cell", p),
+            PathGridIssue::InvalidConnection(p, to) => {
+                write!(f, "Point {} connects to missing point {}", p, to)
+            }
+            PathGridIssue::OneWayConnection(a, b) => {
+                write!(f, "Connection {} -> {} is one-way", a, b)
+            }
+            PathGridIssue::CountMismatch => write!(f, "Point or connection counts are wrong"),
+        }
+    }
+}
+
+/// Returns the connected points of each point
+pub fn get_adjacency(pathgrid: &PathGrid) -> Vec<Vec<usize>> {
+    let mut adjacency = vec![];
+    let mut start = 0;
+    for point in pathgrid.points.iter() {
+        let end = (start + point.connection_count as usize).min(pathgrid.connections.len());
+        let connected = pathgrid.connections[start.min(end)..end]
+            .iter()
+            .map(|c| *c as usize)
+            .filter(|c| *c < pathgrid.points.len())
+            .collect::<Vec<_>>();
+        adjacency.push(connected);
+        start = end;
+    }
+    adjacency
+}
+
+/// The most connections a point can store
+pub const MAX_CONNECTIONS: usize = u8::MAX as usize;
+
+/// Writes the connections back to the pathgrid and updates all counts
+/// Connections beyond [`MAX_CONNECTIONS`] of a point are dropped
+pub fn set_adjacency(pathgrid: &mut PathGrid, adjacency: &[Vec<usize>]) {
+    pathgrid.connections.clear();
+    for (point, connected) in pathgrid.points.iter_mut().zip(adjacency) {
+        let mut connected = connected.clone();
+        connected.sort();
+        connected.dedup();
+        connected.truncate(MAX_CONNECTIONS);
+        point.connection_count = connected.len() as u8;
+        pathgrid
+            .connections
+            .extend(connected.into_iter().map(|c| c as u32));
+    }
+    pathgrid.data.num_points = pathgrid.points.len() as u16;
+}
+
+/// Adds a point and optionally connects it to another point, returns the new index
+/// The point is not connected if the other point has no room for another connection
+pub fn add_point(pathgrid: &mut PathGrid, location: [i32; 3], connect_to: Option<usize>) -> usize {
+    let mut adjacency = get_adjacency(pathgrid);
+    let index = pathgrid.points.len();
+    pathgrid.points.push(tes3::esp::PathGridPoint {
+        location,
+        ..Default::default()
+    });
+    adjacency.push(vec![]);
+    if let Some(other) = connect_to.filter(|o| *o < index && adjacency[*o].len() < MAX_CONNECTIONS)
+    {
+        adjacency[index].push(other);
+        adjacency[other].push(index);
+    }
+    set_adjacency(pathgrid, &adjacency);
+    index
+}
+
+/// Removes a point and all its connections, following points move down by one
+pub fn remove_point(pathgrid: &mut PathGrid, index: usize) {
+    if index >= pathgrid.points.len() {
+        return;
+    }
+    let mut adjacency = get_adjacency(pathgrid);
+    pathgrid.points.remove(index);
+    adjacency.remove(index);
+    for connected in adjacency.iter_mut() {
+        connected.retain(|c| *c != index);
+        for c in connected.iter_mut() {
+            if *c > index {
+                *c -= 1;
+            }
+        }
+    }
+    set_adjacency(pathgrid, &adjacency);
+}
+
+/// Connects two points in both directions, or disconnects them if they are connected
+/// Fails if one of the points can't store another connection
+pub fn toggle_connection(pathgrid: &mut PathGrid, a: usize, b: usize) -> Result<(), String> {
+    let len = pathgrid.points.len();
+    if a == b || a >= len || b >= len {
+        return Ok(());
+    }
+    let mut adjacency = get_adjacency(pathgrid);
+    if adjacency[a].contains(&b) || adjacency[b].contains(&a) {
+        adjacency[a].retain(|c| *c != b);
+        adjacency[b].retain(|c| *c != a);
+    } else {
+        if let Some(full) = [a, b]
+            .into_iter()
+            .find(|p| adjacency[*p].len() >= MAX_CONNECTIONS)
+        {
+            return Err(format!(
+                "Point {} already has {} connections",
+                full, MAX_CONNECTIONS
+            ));
+        }
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+    set_adjacency(pathgrid, &adjacency);
+    Ok(())
+}
+
+/// Exterior pathgrid points are relative to the cell origin
+pub fn get_origin(pathgrid: &PathGrid, cell: Option<&Cell>) -> (f32, f32) {
+    match cell {
+        Some(cell) if !cell.is_interior() => {
+            let (x, y) = pathgrid.data.grid;
+            (x as f32 * EXTERIOR_CELL_SIZE, y as f32 * EXTERIOR_CELL_SIZE)
+        }
+        _ => (0.0, 0.0),
+    }
+}
+
+/// Finds the cell of a pathgrid in the given plugins, later plugins win
+pub fn find_cell(pathgrid: &PathGrid, plugins: &[PluginMetadata]) -> Option<Cell> {
+    let mut result = None;
+    for plugin in plugins {
+        for record in plugin
+            .records
+            .values()
+            .chain(plugin.edited_records.values())
+        {
+            let TES3Object::Cell(cell) = record else {
+                continue;
+            };
+            let matches = if cell.is_interior() {
+                cell.name.eq_ignore_ascii_case(&pathgrid.cell)
+            } else {
+                cell.data.grid == pathgrid.data.grid
+            };
+            if matches {
+                result = Some(cell.clone());
+            }
+        }
+    }
+    result
+}
+
+/// Checks a pathgrid for islands, out of cell points and broken connections
+pub fn validate_pathgrid(pathgrid: &PathGrid, cell: Option<&Cell>) -> Vec<PathGridIssue> {
+    let mut issues = vec![];
+    let len = pathgrid.points.len();
+
+    // counts
+    let connection_count = pathgrid
+        .points
+        .iter()
+        .map(|p| p.connection_count as usize)
+        .sum::<usize>();
+    if pathgrid.data.num_points as usize != len || connection_count != pathgrid.connections.len() {
+        issues.push(PathGridIssue::CountMismatch);
+    }
+
+    // broken connections
+    let mut start = 0;
+    for (i, point) in pathgrid.points.iter().enumerate() {
+        let end = (start + point.connection_count as usize).min(pathgrid.connections.len());
+        for to in pathgrid.connections[start.min(end)..end].iter() {
+            if *to as usize >= len {
+                issues.push(PathGridIssue::InvalidConnection(i, *to));
+            }
+        }
+        start = end;
+    }
+    let adjacency = get_adjacency(pathgrid);
+    for (a, connected) in adjacency.iter().enumerate() {
+        for b in connected.iter() {
+            if !adjacency[*b].contains(&a) {
+                issues.push(PathGridIssue::OneWayConnection(a, *b));
+            }
+        }
+    }
+
+    // points outside of exterior cells
+    if cell.is_some_and(|c| !c.is_interior()) {
+        for (i, point) in pathgrid.points.iter().enumerate() {
+            let [x, y, _] = point.location;
+            let range = 0.0..=EXTERIOR_CELL_SIZE;
+            if !range.contains(&(x as f32)) || !range.contains(&(y as f32)) {
+                issues.push(PathGridIssue::OutOfCell(i));
+            }
+        }
+    }
+
+    // islands: all groups of connected points except the largest one
+    // one-way connections count as connected here
+    let mut undirected = adjacency.clone();
+    for (a, connected) in adjacency.iter().enumerate() {
+        for b in connected.iter() {
+            undirected[*b].push(a);
+        }
+    }
+    let mut group = vec![usize::MAX; len];
+    let mut groups: Vec<Vec<usize>> = vec![];
+    for start in 0..len {
+        if group[start] != usize::MAX {
+            continue;
+        }
+        let id = groups.len();
+        let mut members = vec![];
+        let mut stack = vec![start];
+        group[start] = id;
+        while let Some(p) = stack.pop() {
+            members.push(p);
+            for q in undirected[p].iter().copied() {
+                if group[q] == usize::MAX {
+                    group[q] = id;
+                    stack.push(q);
+                }
+            }
+        }
+        members.sort();
+        groups.push(members);
+    }
+    if let Some(largest) = groups
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, g)| g.len())
+        .map(|(i, _)| i)
+    {
+        for (i, members) in groups.into_iter().enumerate() {
+            if i != largest {
+                issues.push(PathGridIssue::Island(members));
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use tes3::esp::{CellData, PathGridPoint};
+
+    use super::*;
+
+    /// A pathgrid with the points at the given locations and connections
+    fn pathgrid(points: &[([i32; 3], &[u32])]) -> PathGrid {
+        let mut pathgrid = PathGrid::default();
+        for (location, connected) in points {
+            pathgrid.points.push(PathGridPoint {
+                location: *location,
+                connection_count: connected.len() as u8,
+                ..Default::default()
+            });
+            pathgrid.connections.extend(connected.iter());
+        }
+        pathgrid.data.num_points = points.len() as u16;
+        pathgrid
+    }
+
+    fn exterior() -> Cell {
+        Cell {
+            data: CellData {
+                grid: (2, -3),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn valid_pathgrid() {
+        let pathgrid = pathgrid(&[
+            ([0, 0, 0], &[1]),
+            ([100, 0, 0], &[0, 2]),
+            ([100, 100, 0], &[1]),
+        ]);
+        assert_eq!(validate_pathgrid(&pathgrid, Some(&exterior())), vec![]);
+    }
+
+    #[test]
+    fn islands() {
+        let pathgrid = pathgrid(&[
+            ([0, 0, 0], &[1]),
+            ([100, 0, 0], &[0, 2]),
+            ([100, 100, 0], &[1]),
+            ([500, 500, 0], &[4]),
+            ([600, 500, 0], &[3]),
+            ([900, 900, 0], &[]),
+        ]);
+        assert_eq!(
+            validate_pathgrid(&pathgrid, None),
+            vec![
+                PathGridIssue::Island(vec![3, 4]),
+                PathGridIssue::Island(vec![5])
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_cell() {
+        let pathgrid = pathgrid(&[
+            ([0, 0, 0], &[1]),
+            ([-10, 0, 0], &[0, 2]),
+            ([100, EXTERIOR_CELL_SIZE as i32 + 1, 0], &[1]),
+        ]);
+        assert_eq!(
+            validate_pathgrid(&pathgrid, Some(&exterior())),
+            vec![PathGridIssue::OutOfCell(1), PathGridIssue::OutOfCell(2)]
+        );
+        // interiors have no bounds
+        assert_eq!(validate_pathgrid(&pathgrid, None), vec![]);
+    }
+
+    #[test]
+    fn one_way_and_invalid_connections() {
+        let pathgrid = pathgrid(&[([0, 0, 0], &[1, 7]), ([100, 0, 0], &[])]);
+        assert_eq!(
+            validate_pathgrid(&pathgrid, None),
+            vec![
+                PathGridIssue::InvalidConnection(0, 7),
+                PathGridIssue::OneWayConnection(0, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn count_mismatch() {
+        let mut points = pathgrid(&[([0, 0, 0], &[1]), ([100, 0, 0], &[0])]);
+        points.data.num_points = 3;
+        assert_eq!(
+            validate_pathgrid(&points, None),
+            vec![PathGridIssue::CountMismatch]
+        );
+
+        let mut connections = pathgrid(&[([0, 0, 0], &[1]), ([100, 0, 0], &[0])]);
+        connections.connections.push(0);
+        assert_eq!(
+            validate_pathgrid(&connections, None),
+            vec![PathGridIssue::CountMismatch]
+        );
+    }
+
+    #[test]
+    fn connection_limit() {
+        let mut pathgrid = pathgrid(&[([0, 0, 0], &[])]);
+        for i in 0..MAX_CONNECTIONS {
+            add_point(&mut pathgrid, [i as i32, 10, 0], Some(0));
+        }
+        assert_eq!(
+            pathgrid.points[0].connection_count as usize,
+            MAX_CONNECTIONS
+        );
+
+        // the first point is full
+        let last = add_point(&mut pathgrid, [0, 20, 0], Some(0));
+        assert_eq!(pathgrid.points[last].connection_count, 0);
+        assert!(toggle_connection(&mut pathgrid, 0, last).is_err());
+        assert_eq!(
+            pathgrid.points[0].connection_count as usize,
+            MAX_CONNECTIONS
+        );
+
+        // removing a connection is always possible
+        assert!(toggle_connection(&mut pathgrid, 0, 1).is_ok());
+        assert_eq!(
+            pathgrid.points[0].connection_count as usize,
+            MAX_CONNECTIONS - 1
+        );
+        assert!(validate_pathgrid(&pathgrid, None)
+            .iter()
+            .all(|issue| matches!(issue, PathGridIssue::Island(_))));
+    }
+}
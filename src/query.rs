@@ -0,0 +1,490 @@
+use std::fmt::Display;
+
+use tes3::esp::{TES3Object, TypeInfo};
+
+use crate::{get_all_tags, get_record_fields, get_unique_id};
+
+/// Comparison operators of a query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contains,
+}
+
+impl Display for EOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EOperator::Equal => write!(f, "="),
+            EOperator::NotEqual => write!(f, "!="),
+            EOperator::Less => write!(f, "<"),
+            EOperator::LessOrEqual => write!(f, "<="),
+            EOperator::Greater => write!(f, ">"),
+            EOperator::GreaterOrEqual => write!(f, ">="),
+            EOperator::Contains => write!(f, "~"),
+        }
+    }
+}
+
+/// A filter expression over the fields of a record
+#[derive(Debug, Clone, PartialEq)]
+pub enum EExpression {
+    And(Box<EExpression>, Box<EExpression>),
+    Or(Box<EExpression>, Box<EExpression>),
+    Not(Box<EExpression>),
+    /// a field path, matched by its full name or its last parts
+    Compare(String, EOperator, String),
+}
+
+/// A parsed query: an optional record tag and an optional filter
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub tag: Option<String>,
+    pub expression: Option<EExpression>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EToken {
+    Word(String),
+    Text(String),
+    Operator(EOperator),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<EToken>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => EToken::Open,
+            ')' => EToken::Close,
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => return Err("missing closing quote".into()),
+                    }
+                }
+                EToken::Text(value)
+            }
+            '=' => {
+                chars.next_if_eq(&'=');
+                EToken::Operator(EOperator::Equal)
+            }
+            '~' => EToken::Operator(EOperator::Contains),
+            '!' if chars.next_if_eq(&'=').is_some() => EToken::Operator(EOperator::NotEqual),
+            '<' if chars.next_if_eq(&'=').is_some() => EToken::Operator(EOperator::LessOrEqual),
+            '>' if chars.next_if_eq(&'=').is_some() => EToken::Operator(EOperator::GreaterOrEqual),
+            '<' => EToken::Operator(EOperator::Less),
+            '>' => EToken::Operator(EOperator::Greater),
+            c if c.is_alphanumeric() || "_.-+".contains(c) => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || "_.-+".contains(*c)) {
+                    word.push(c);
+                }
+                EToken::Word(word)
+            }
+            c => return Err(format!("unexpected '{}'", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<EToken>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.position), Some(EToken::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self) -> Option<EToken> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<EExpression, String> {
+        let mut left = self.and()?;
+        while self.peek_keyword("or") {
+            self.position += 1;
+            left = EExpression::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<EExpression, String> {
+        let mut left = self.unary()?;
+        while self.peek_keyword("and") {
+            self.position += 1;
+            left = EExpression::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<EExpression, String> {
+        if self.peek_keyword("not") {
+            self.position += 1;
+            return Ok(EExpression::Not(Box::new(self.unary()?)));
+        }
+
+        match self.next() {
+            Some(EToken::Open) => {
+                let expression = self.or()?;
+                match self.next() {
+                    Some(EToken::Close) => Ok(expression),
+                    _ => Err("missing ')'".into()),
+                }
+            }
+            Some(EToken::Word(path)) => {
+                let Some(EToken::Operator(operator)) = self.next() else {
+                    return Err(format!("expected an operator after '{}'", path));
+                };
+                match self.next() {
+                    Some(EToken::Word(value)) | Some(EToken::Text(value)) => {
+                        Ok(EExpression::Compare(path, operator, value))
+                    }
+                    _ => Err(format!("expected a value after '{} {}'", path, operator)),
+                }
+            }
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of the query".into()),
+        }
+    }
+}
+
+/// Parses a query like `WEAP where data.damage > 30 and value < 100`
+/// The tag is optional, a query of only a tag returns all records of that type
+pub fn parse_query(text: &str) -> Result<Query, String> {
+    let tokens = tokenize(text)?;
+    let mut query = Query::default();
+    let mut position = 0;
+
+    // record tag
+    if let Some(EToken::Word(word)) = tokens.first() {
+        let is_tag = get_all_tags().contains(word);
+        let next = tokens.get(1);
+        if is_tag && next.is_none() {
+            query.tag = Some(word.clone());
+            return Ok(query);
+        }
+        if is_tag && matches!(next, Some(EToken::Word(w)) if w.eq_ignore_ascii_case("where")) {
+            query.tag = Some(word.clone());
+            position = 2;
+        }
+    }
+
+    let mut parser = Parser { tokens, position };
+    if parser.position < parser.tokens.len() {
+        query.expression = Some(parser.or()?);
+    }
+    if parser.position < parser.tokens.len() {
+        return Err(format!("unexpected {:?}", parser.tokens[parser.position]));
+    }
+    Ok(query)
+}
+
+/// Compares a field value as number if both sides are numbers, else as text
+fn compare(field: &str, operator: EOperator, value: &str) -> bool {
+    let field = field.trim_matches('"');
+    if let (Ok(a), Ok(b)) = (field.parse::<f64>(), value.parse::<f64>()) {
+        return match operator {
+            EOperator::Equal => a == b,
+            EOperator::NotEqual => a != b,
+            EOperator::Less => a < b,
+            EOperator::LessOrEqual => a <= b,
+            EOperator::Greater => a > b,
+            EOperator::GreaterOrEqual => a >= b,
+            EOperator::Contains => field.contains(value),
+        };
+    }
+
+    let field = field.to_lowercase();
+    let value = value.to_lowercase();
+    match operator {
+        EOperator::Equal => field == value,
+        EOperator::NotEqual => field != value,
+        EOperator::Less => field < value,
+        EOperator::LessOrEqual => field <= value,
+        EOperator::Greater => field > value,
+        EOperator::GreaterOrEqual => field >= value,
+        EOperator::Contains => field.contains(&value),
+    }
+}
+
+/// Whether a dotted field name is addressed by a query path
+fn is_path_match(field_name: &str, path: &str) -> bool {
+    let field_name = field_name.to_lowercase();
+    let path = path.to_lowercase();
+    field_name == path || field_name.ends_with(&format!(".{}", path))
+}
+
+impl EExpression {
+    /// A comparison is true if any field with a matching path satisfies it
+    pub fn evaluate(&self, fields: &[(String, String)]) -> bool {
+        match self {
+            EExpression::And(a, b) => a.evaluate(fields) && b.evaluate(fields),
+            EExpression::Or(a, b) => a.evaluate(fields) || b.evaluate(fields),
+            EExpression::Not(a) => !a.evaluate(fields),
+            EExpression::Compare(path, operator, value) => fields
+                .iter()
+                .filter(|(name, _)| is_path_match(name, path))
+                .any(|(_, field)| compare(field, *operator, value)),
+        }
+    }
+
+    /// Field paths used by the expression
+    fn get_paths<'a>(&'a self, paths: &mut Vec<&'a str>) {
+        match self {
+            EExpression::And(a, b) | EExpression::Or(a, b) => {
+                a.get_paths(paths);
+                b.get_paths(paths);
+            }
+            EExpression::Not(a) => a.get_paths(paths),
+            EExpression::Compare(path, _, _) => paths.push(path),
+        }
+    }
+}
+
+impl Query {
+    pub fn matches(&self, record: &TES3Object) -> bool {
+        if self
+            .tag
+            .as_ref()
+            .is_some_and(|tag| tag.as_str() != record.tag_str())
+        {
+            return false;
+        }
+        match &self.expression {
+            Some(expression) => expression.evaluate(&get_record_fields(record)),
+            None => true,
+        }
+    }
+
+    /// The values of the fields the query uses, to show next to the results
+    pub fn get_used_fields(&self, record: &TES3Object) -> Vec<(String, String)> {
+        let mut paths = vec![];
+        if let Some(expression) = &self.expression {
+            expression.get_paths(&mut paths);
+        }
+        get_record_fields(record)
+            .into_iter()
+            .filter(|(name, _)| paths.iter().any(|path| is_path_match(name, path)))
+            .collect()
+    }
+}
+
+/// A record that matches a query
+#[derive(Debug, Clone)]
+pub struct QueryResult {
+    pub plugin: String,
+    pub record_id: String,
+    /// values of the fields used in the query
+    pub fields: Vec<(String, String)>,
+    pub record: TES3Object,
+}
+
+/// Runs the query over records with their plugin name
+pub fn run_query<'a>(
+    query: &Query,
+    records: impl Iterator<Item = (&'a str, &'a TES3Object)>,
+) -> Vec<QueryResult> {
+    records
+        .filter(|(_, record)| query.matches(record))
+        .map(|(plugin, record)| QueryResult {
+            plugin: plugin.to_owned(),
+            record_id: get_unique_id(record),
+            fields: query.get_used_fields(record),
+            record: record.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(path: &str, operator: EOperator, value: &str) -> EExpression {
+        EExpression::Compare(path.into(), operator, value.into())
+    }
+
+    fn and(a: EExpression, b: EExpression) -> EExpression {
+        EExpression::And(Box::new(a), Box::new(b))
+    }
+
+    fn or(a: EExpression, b: EExpression) -> EExpression {
+        EExpression::Or(Box::new(a), Box::new(b))
+    }
+
+    fn not(a: EExpression) -> EExpression {
+        EExpression::Not(Box::new(a))
+    }
+
+    fn fields(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+        fields
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Parses a filter and evaluates it on the fields
+    fn evaluate(query: &str, record: &[(&str, &str)]) -> bool {
+        let query = parse_query(query).unwrap();
+        query.expression.unwrap().evaluate(&fields(record))
+    }
+
+    #[test]
+    fn tags() {
+        let query = parse_query("WEAP").unwrap();
+        assert_eq!(query.tag.as_deref(), Some("WEAP"));
+        assert_eq!(query.expression, None);
+
+        let query = parse_query("TES3 where author ~ me").unwrap();
+        assert_eq!(query.tag.as_deref(), Some("TES3"));
+
+        let query = parse_query("NPC_ where level < 5").unwrap();
+        assert_eq!(query.tag.as_deref(), Some("NPC_"));
+        assert_eq!(
+            query.expression,
+            Some(compare("level", EOperator::Less, "5"))
+        );
+
+        // without where the first word is a field
+        let query = parse_query("name = WEAP").unwrap();
+        assert_eq!(query.tag, None);
+
+        // only record tags are tags
+        assert!(parse_query("ABC_ where level < 5").is_err());
+    }
+
+    #[test]
+    fn precedence() {
+        let query = parse_query("a = 1 or b = 2 and c = 3").unwrap();
+        assert_eq!(
+            query.expression,
+            Some(or(
+                compare("a", EOperator::Equal, "1"),
+                and(
+                    compare("b", EOperator::Equal, "2"),
+                    compare("c", EOperator::Equal, "3")
+                )
+            ))
+        );
+
+        let query = parse_query("(a = 1 or b = 2) and not c != 3").unwrap();
+        assert_eq!(
+            query.expression,
+            Some(and(
+                or(
+                    compare("a", EOperator::Equal, "1"),
+                    compare("b", EOperator::Equal, "2")
+                ),
+                not(compare("c", EOperator::NotEqual, "3"))
+            ))
+        );
+
+        let query = parse_query("not not a == 1 AND b ~ \"two words\"").unwrap();
+        assert_eq!(
+            query.expression,
+            Some(and(
+                not(not(compare("a", EOperator::Equal, "1"))),
+                compare("b", EOperator::Contains, "two words")
+            ))
+        );
+    }
+
+    #[test]
+    fn operators() {
+        for (text, operator) in [
+            ("=", EOperator::Equal),
+            ("==", EOperator::Equal),
+            ("!=", EOperator::NotEqual),
+            ("<", EOperator::Less),
+            ("<=", EOperator::LessOrEqual),
+            (">", EOperator::Greater),
+            (">=", EOperator::GreaterOrEqual),
+            ("~", EOperator::Contains),
+        ] {
+            let query = parse_query(&format!("value {} 10", text)).unwrap();
+            assert_eq!(query.expression, Some(compare("value", operator, "10")));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        for query in [
+            "value >",
+            "value 10",
+            "(value > 10",
+            "value > 10)",
+            "value > 10 and",
+            "name = \"open",
+            "value > 10 # 2",
+        ] {
+            assert!(parse_query(query).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn numbers_and_text() {
+        // numbers are compared as numbers, 9 < 10
+        assert!(evaluate("value < 10", &[("data.value", "9")]));
+        assert!(evaluate("value = 1.5", &[("data.value", "1.50")]));
+        assert!(!evaluate("value > 10", &[("data.value", "9")]));
+
+        // text is compared without case, quotes of the field value are ignored
+        assert!(evaluate(
+            "name = \"iron dagger\"",
+            &[("name", "\"Iron Dagger\"")]
+        ));
+        assert!(evaluate("name ~ DAGGER", &[("name", "\"Iron Dagger\"")]));
+        assert!(evaluate("name < b", &[("name", "\"Apple\"")]));
+        // mixed values are compared as text
+        assert!(evaluate("name != 10", &[("name", "\"ten\"")]));
+    }
+
+    #[test]
+    fn paths() {
+        // full paths and trailing parts of a path
+        let record = [("data.damage.chop_max", "35"), ("id", "\"dagger\"")];
+        assert!(evaluate("data.damage.chop_max > 30", &record));
+        assert!(evaluate("chop_max > 30", &record));
+        assert!(evaluate("damage.chop_max > 30", &record));
+        assert!(!evaluate("max > 30", &record));
+        // missing fields never match, not even for !=
+        assert!(!evaluate("value != 1", &record));
+        assert!(evaluate("not value = 1", &record));
+    }
+
+    #[test]
+    fn weapon_damage_and_npc_faction() {
+        // all WEAP with damage > 30 and value < 100
+        let query = "WEAP where chop_max > 30 and value < 100";
+        let weapon =
+            |damage: &str, value: &str| fields(&[("data.chop_max", damage), ("data.value", value)]);
+        let expression = parse_query(query).unwrap().expression.unwrap();
+        assert!(expression.evaluate(&weapon("35", "80")));
+        assert!(!expression.evaluate(&weapon("30", "80")));
+        assert!(!expression.evaluate(&weapon("35", "100")));
+
+        // NPCs in faction X with level < 5
+        let query = "NPC_ where faction = \"Hlaalu\" and level < 5";
+        let npc =
+            |faction: &str, level: &str| fields(&[("faction", faction), ("data.level", level)]);
+        let expression = parse_query(query).unwrap().expression.unwrap();
+        assert!(expression.evaluate(&npc("\"Hlaalu\"", "4")));
+        assert!(!expression.evaluate(&npc("\"Redoran\"", "4")));
+        assert!(!expression.evaluate(&npc("\"Hlaalu\"", "5")));
+    }
+}
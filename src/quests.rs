@@ -0,0 +1,163 @@
+use tes3::esp::{DialogueType, QuestState, TES3Object};
+
+use crate::{dialogue::get_topic_infos, PluginMetadata};
+
+/// A journal entry of a quest
+#[derive(Debug, Clone)]
+pub struct QuestStage {
+    pub plugin_id: String,
+    /// unique id of the INFO
+    pub info_id: String,
+    pub index: u32,
+    pub text: String,
+    pub finished: bool,
+    pub restart: bool,
+}
+
+/// A journal topic merged over all open plugins
+#[derive(Debug, Clone, Default)]
+pub struct Quest {
+    pub id: String,
+    /// the text of the quest name INFO
+    pub name: Option<String>,
+    pub stages: Vec<QuestStage>,
+}
+
+/// A dialogue result or script line that changes a journal
+#[derive(Debug, Clone)]
+pub struct JournalCall {
+    pub plugin_id: String,
+    /// unique id of the INFO or SCPT
+    pub record_id: String,
+    pub line: String,
+    pub index: Option<i32>,
+}
+
+/// Collects all journals of the open plugins with their stages in chain order
+/// Later plugins replace stages with the same INFO id
+pub fn get_quests(plugins: &[PluginMetadata]) -> Vec<Quest> {
+    let mut quests: Vec<Quest> = vec![];
+    for plugin in plugins {
+        let journals = plugin
+            .records
+            .iter()
+            .chain(plugin.edited_records.iter())
+            .filter_map(|(id, record)| match record {
+                TES3Object::Dialogue(dialogue)
+                    if dialogue.dialogue_type == DialogueType::Journal =>
+                {
+                    Some((id.clone(), dialogue.id.clone()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for (topic_id, name) in journals {
+            let index = match quests.iter().position(|q| q.id.eq_ignore_ascii_case(&name)) {
+                Some(index) => index,
+                None => {
+                    quests.push(Quest {
+                        id: name.clone(),
+                        ..Default::default()
+                    });
+                    quests.len() - 1
+                }
+            };
+            let quest = &mut quests[index];
+
+            for info_id in get_topic_infos(plugin, &topic_id) {
+                let record = plugin
+                    .edited_records
+                    .get(&info_id)
+                    .or_else(|| plugin.records.get(&info_id));
+                let Some(TES3Object::DialogueInfo(info)) = record else {
+                    continue;
+                };
+
+                if info.quest_state == Some(QuestState::Name) {
+                    quest.name = Some(info.text.clone());
+                    continue;
+                }
+
+                let stage = QuestStage {
+                    plugin_id: plugin.id.clone(),
+                    info_id: info_id.clone(),
+                    // journal indices are stored in the disposition field
+                    index: info.data.disposition,
+                    text: info.text.clone(),
+                    finished: info.quest_state == Some(QuestState::Finished),
+                    restart: info.quest_state == Some(QuestState::Restart),
+                };
+                match quest.stages.iter_mut().find(|s| s.info_id == info_id) {
+                    Some(existing) => *existing = stage,
+                    None => quest.stages.push(stage),
+                }
+            }
+        }
+    }
+
+    for quest in quests.iter_mut() {
+        quest.stages.sort_by_key(|s| s.index);
+    }
+    quests.sort_by_key(|q| q.id.to_lowercase());
+    quests
+}
+
+/// Returns the quest id and index if the line calls Journal or SetJournalIndex
+pub fn parse_journal_call(line: &str) -> Option<(String, Option<i32>)> {
+    // remove comments and an explicit reference like player->Journal
+    let code = line.split(';').next().unwrap_or_default();
+    let code = code.rsplit("->").next().unwrap_or_default();
+
+    let mut tokens = code
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .map(|t| t.trim_matches('"'));
+    let function = tokens.next()?.to_lowercase();
+    if function != "journal" && function != "setjournalindex" {
+        return None;
+    }
+    let quest = tokens.next()?.to_owned();
+    let index = tokens.next().and_then(|t| t.parse::<i32>().ok());
+    Some((quest, index))
+}
+
+/// Searches dialogue results and scripts of the open plugins for journal changes of a quest
+pub fn find_journal_calls(plugins: &[PluginMetadata], quest_id: &str) -> Vec<JournalCall> {
+    let mut calls = vec![];
+    for plugin in plugins {
+        let mut ids = plugin
+            .records
+            .keys()
+            .chain(plugin.edited_records.keys())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+
+        for id in ids {
+            let record = plugin
+                .edited_records
+                .get(id)
+                .or_else(|| plugin.records.get(id));
+            let text = match record {
+                Some(TES3Object::DialogueInfo(info)) => &info.script_text,
+                Some(TES3Object::Script(script)) => &script.text,
+                _ => continue,
+            };
+
+            for line in text.lines() {
+                if let Some((quest, index)) = parse_journal_call(line) {
+                    if quest.eq_ignore_ascii_case(quest_id) {
+                        calls.push(JournalCall {
+                            plugin_id: plugin.id.clone(),
+                            record_id: id.clone(),
+                            line: line.trim().to_owned(),
+                            index,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    calls
+}
@@ -0,0 +1,846 @@
+use std::{fmt::Display, ops::Range};
+
+use tes3::esp::TES3Object;
+
+use crate::PluginMetadata;
+
+const KEYWORDS: [&str; 14] = [
+    "begin", "end", "if", "elseif", "else", "endif", "while", "endwhile", "short", "long", "float",
+    "set", "to", "return",
+];
+
+/// Stats that have a Get, Set and Mod function
+const STATS: &[&str] = &[
+    "Strength",
+    "Intelligence",
+    "Willpower",
+    "Agility",
+    "Speed",
+    "Endurance",
+    "Personality",
+    "Luck",
+    "Block",
+    "Armorer",
+    "MediumArmor",
+    "HeavyArmor",
+    "BluntWeapon",
+    "LongBlade",
+    "Axe",
+    "Spear",
+    "Athletics",
+    "Enchant",
+    "Destruction",
+    "Alteration",
+    "Illusion",
+    "Conjuration",
+    "Mysticism",
+    "Restoration",
+    "Alchemy",
+    "Unarmored",
+    "Security",
+    "Sneak",
+    "Acrobatics",
+    "LightArmor",
+    "ShortBlade",
+    "Marksman",
+    "Mercantile",
+    "Speechcraft",
+    "HandToHand",
+    "Health",
+    "Magicka",
+    "Fatigue",
+    "Reputation",
+    "Disposition",
+    "Fight",
+    "Flee",
+    "Alarm",
+    "Hello",
+    "AttackBonus",
+    "DefendBonus",
+    "ArmorBonus",
+    "CastPenalty",
+    "Chameleon",
+    "Invisible",
+    "Blindness",
+    "Paralysis",
+    "Silence",
+    "Flying",
+    "SuperJump",
+    "SwimSpeed",
+    "WaterBreathing",
+    "WaterWalking",
+    "ResistBlight",
+    "ResistCorprus",
+    "ResistDisease",
+    "ResistFire",
+    "ResistFrost",
+    "ResistMagicka",
+    "ResistNormalWeapons",
+    "ResistParalysis",
+    "ResistPoison",
+    "ResistShock",
+];
+
+/// Script functions of the base game and its expansions, without the stat functions
+const FUNCTIONS: &[&str] = &[
+    "Activate",
+    "AddItem",
+    "AddSoulGem",
+    "AddSpell",
+    "AddToLevCreature",
+    "AddToLevItem",
+    "AddTopic",
+    "AIActivate",
+    "AIEscort",
+    "AIEscortCell",
+    "AIFollow",
+    "AIFollowCell",
+    "AITravel",
+    "AIWander",
+    "BecomeWerewolf",
+    "Cast",
+    "CellChanged",
+    "CenterOnCell",
+    "CenterOnExterior",
+    "ChangeWeather",
+    "Choice",
+    "ClearForceJump",
+    "ClearForceMoveJump",
+    "ClearForceRun",
+    "ClearForceSneak",
+    "ClearInfoActor",
+    "Disable",
+    "DisableLevitation",
+    "DisablePlayerControls",
+    "DisablePlayerFighting",
+    "DisablePlayerJumping",
+    "DisablePlayerLooking",
+    "DisablePlayerMagic",
+    "DisablePlayerViewSwitch",
+    "DisableTeleporting",
+    "DisableVanityMode",
+    "DontSaveObject",
+    "Drop",
+    "DropSoulGem",
+    "Enable",
+    "EnableBirthMenu",
+    "EnableClassMenu",
+    "EnableInventoryMenu",
+    "EnableLevelUpMenu",
+    "EnableLevitation",
+    "EnableMagicMenu",
+    "EnableMapMenu",
+    "EnableNameMenu",
+    "EnablePlayerControls",
+    "EnablePlayerFighting",
+    "EnablePlayerJumping",
+    "EnablePlayerLooking",
+    "EnablePlayerMagic",
+    "EnablePlayerViewSwitch",
+    "EnableRaceMenu",
+    "EnableRest",
+    "EnableStatReviewMenu",
+    "EnableStatsMenu",
+    "EnableTeleporting",
+    "EnableVanityMode",
+    "Equip",
+    "ExplodeSpell",
+    "Face",
+    "FadeIn",
+    "FadeOut",
+    "FadeTo",
+    "Fall",
+    "ForceGreeting",
+    "ForceJump",
+    "ForceMoveJump",
+    "ForceRun",
+    "ForceSneak",
+    "GetAIPackageDone",
+    "GetAngle",
+    "GetArmorType",
+    "GetAttacked",
+    "GetBlightDisease",
+    "GetButtonPressed",
+    "GetCollidingActor",
+    "GetCollidingPC",
+    "GetCommonDisease",
+    "GetCurrentAIPackage",
+    "GetCurrentTime",
+    "GetCurrentWeather",
+    "GetDeadCount",
+    "GetDetected",
+    "GetDisabled",
+    "GetDistance",
+    "GetEffect",
+    "GetFactionReaction",
+    "GetForceJump",
+    "GetForceMoveJump",
+    "GetForceRun",
+    "GetForceSneak",
+    "GetHealthRatio",
+    "GetInterior",
+    "GetItemCount",
+    "GetJournalIndex",
+    "GetLevel",
+    "GetLineOfSight",
+    "GetLocked",
+    "GetLOS",
+    "GetMasserPhase",
+    "GetPCCell",
+    "GetPCCrimeLevel",
+    "GetPCFacRep",
+    "GetPCInJail",
+    "GetPCJumping",
+    "GetPCRank",
+    "GetPCRunning",
+    "GetPCSleep",
+    "GetPCSneaking",
+    "GetPCTraveling",
+    "GetPlayerControlsDisabled",
+    "GetPlayerFightingDisabled",
+    "GetPlayerJumpingDisabled",
+    "GetPlayerLookingDisabled",
+    "GetPlayerMagicDisabled",
+    "GetPos",
+    "GetRace",
+    "GetScale",
+    "GetSecondsPassed",
+    "GetSecundaPhase",
+    "GetSoundPlaying",
+    "GetSpell",
+    "GetSpellEffects",
+    "GetSpellReadied",
+    "GetSquareRoot",
+    "GetStandingActor",
+    "GetStandingPC",
+    "GetStartingAngle",
+    "GetStartingPos",
+    "GetTarget",
+    "GetVanityModeDisabled",
+    "GetWaterLevel",
+    "GetWeaponDrawn",
+    "GetWeaponType",
+    "GetWerewolfKills",
+    "GetWindSpeed",
+    "GoodBye",
+    "GotoJail",
+    "HasItemEquipped",
+    "HasSoulGem",
+    "HitAttemptOnMe",
+    "HitOnMe",
+    "HurtCollidingActor",
+    "HurtStandingActor",
+    "IsWerewolf",
+    "Journal",
+    "Lock",
+    "LoopGroup",
+    "LowerRank",
+    "MenuMode",
+    "MessageBox",
+    "ModCurrentFatigue",
+    "ModCurrentHealth",
+    "ModCurrentMagicka",
+    "ModFactionReaction",
+    "ModPCCrimeLevel",
+    "ModPCFacRep",
+    "ModRegion",
+    "ModScale",
+    "ModWaterLevel",
+    "Move",
+    "MoveWorld",
+    "OnActivate",
+    "OnDeath",
+    "OnKnockout",
+    "OnMurder",
+    "OnPCAdd",
+    "OnPCDrop",
+    "OnPCEquip",
+    "OnPCHitMe",
+    "OnPCRepair",
+    "OnPCSoulGemUse",
+    "OnRepair",
+    "PayFine",
+    "PayFineThief",
+    "PCClearExpelled",
+    "PCExpell",
+    "PCExpelled",
+    "PCForce1stPerson",
+    "PCForce3rdPerson",
+    "PCGet3rdPerson",
+    "PCJoinFaction",
+    "PCLowerRank",
+    "PCRaiseRank",
+    "PlaceAtMe",
+    "PlaceAtPC",
+    "PlaceItem",
+    "PlaceItemCell",
+    "PlayBink",
+    "PlayGroup",
+    "PlayLoopSound3D",
+    "PlayLoopSound3DVP",
+    "PlaySound",
+    "PlaySound3D",
+    "PlaySound3DVP",
+    "PlaySoundVP",
+    "Position",
+    "PositionCell",
+    "RaiseRank",
+    "Random",
+    "RemoveEffects",
+    "RemoveFromLevCreature",
+    "RemoveFromLevItem",
+    "RemoveItem",
+    "RemoveSoulGem",
+    "RemoveSpell",
+    "RemoveSpellEffects",
+    "RepairedOnMe",
+    "ResetActors",
+    "Resurrect",
+    "Rotate",
+    "RotateWorld",
+    "SameFaction",
+    "Say",
+    "SayDone",
+    "ScriptRunning",
+    "SetAngle",
+    "SetAtStart",
+    "SetDelete",
+    "SetFactionReaction",
+    "SetJournalIndex",
+    "SetLevel",
+    "SetPCCrimeLevel",
+    "SetPCFacRep",
+    "SetPos",
+    "SetScale",
+    "SetWaterLevel",
+    "SetWerewolfAcrobatics",
+    "ShowMap",
+    "ShowRestMenu",
+    "SkipAnim",
+    "StartCombat",
+    "StartScript",
+    "StopCombat",
+    "StopScript",
+    "StopSound",
+    "StreamMusic",
+    "TurnMoonRed",
+    "TurnMoonWhite",
+    "UndoWerewolf",
+    "Unlock",
+    "WakeUpPC",
+    "XBox",
+    "GetPCVisionBonus",
+    "SetPCVisionBonus",
+];
+
+/// Globals defined by the base game that scripts commonly use
+const ENGINE_GLOBALS: &[&str] = &[
+    "GameHour",
+    "Day",
+    "Month",
+    "Year",
+    "DaysPassed",
+    "TimeScale",
+    "PCRace",
+    "PCVampire",
+    "PCWerewolf",
+    "PCKnownWerewolf",
+    "CharGenState",
+    "Random100",
+    "NPCVoiceDistance",
+    "VampClan",
+    "WerewolfClawMult",
+    "PCHasCrimeGold",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ETokenKind {
+    Keyword,
+    Function,
+    Identifier,
+    Number,
+    String,
+    Comment,
+    Operator,
+    Bracket,
+}
+
+/// A token of the script text with its byte range
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: ETokenKind,
+    pub range: Range<usize>,
+    pub line: usize,
+}
+
+/// An error found by the validation pass
+#[derive(Debug, Clone)]
+pub struct ScriptError {
+    /// zero based line of the error
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line + 1, self.message)
+    }
+}
+
+pub fn is_keyword(name: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(name))
+}
+
+pub fn is_function(name: &str) -> bool {
+    if FUNCTIONS.iter().any(|f| f.eq_ignore_ascii_case(name)) {
+        return true;
+    }
+
+    // stat functions like GetStrength or ModHealth
+    let name = name.to_lowercase();
+    ["get", "set", "mod"].iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .is_some_and(|stat| STATS.iter().any(|s| s.eq_ignore_ascii_case(stat)))
+    })
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Splits a script into tokens, whitespace is skipped
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    let mut line = 0;
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            ';' => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                ETokenKind::Comment
+            }
+            '"' => {
+                while chars.next_if(|(_, c)| *c != '"' && *c != '\n').is_some() {}
+                chars.next_if(|(_, c)| *c == '"');
+                ETokenKind::String
+            }
+            '(' | ')' => ETokenKind::Bracket,
+            c if is_identifier_char(c) => {
+                let mut is_number = c.is_ascii_digit();
+                while let Some((_, c)) = chars.next_if(|(_, c)| is_identifier_char(*c)) {
+                    is_number &= c.is_ascii_digit();
+                }
+                if is_number {
+                    // decimals
+                    if chars.next_if(|(_, c)| *c == '.').is_some() {
+                        while chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {}
+                    }
+                    ETokenKind::Number
+                } else {
+                    let end = chars.peek().map(|(i, _)| *i).unwrap_or(text.len());
+                    let word = &text[start..end];
+                    if is_keyword(word) {
+                        ETokenKind::Keyword
+                    } else if is_function(word) {
+                        ETokenKind::Function
+                    } else {
+                        ETokenKind::Identifier
+                    }
+                }
+            }
+            '-' => {
+                chars.next_if(|(_, c)| *c == '>');
+                ETokenKind::Operator
+            }
+            '<' | '>' | '=' | '!' => {
+                chars.next_if(|(_, c)| *c == '=');
+                ETokenKind::Operator
+            }
+            _ => ETokenKind::Operator,
+        };
+
+        let end = chars.peek().map(|(i, _)| *i).unwrap_or(text.len());
+        tokens.push(Token {
+            kind,
+            range: start..end,
+            line,
+        });
+    }
+    tokens
+}
+
+/// Groups the tokens by line, comments are dropped
+pub(crate) fn get_lines(tokens: &[Token]) -> Vec<(usize, Vec<&Token>)> {
+    let mut lines: Vec<(usize, Vec<&Token>)> = vec![];
+    for token in tokens.iter().filter(|t| t.kind != ETokenKind::Comment) {
+        match lines.last_mut() {
+            Some((line, line_tokens)) if *line == token.line => line_tokens.push(token),
+            _ => lines.push((token.line, vec![token])),
+        }
+    }
+    lines
+}
+
+/// Returns the ids of all global variables in the plugins
+pub fn get_globals(plugins: &[PluginMetadata]) -> Vec<String> {
+    let mut globals = plugins
+        .iter()
+        .flat_map(|p| p.records.values().chain(p.edited_records.values()))
+        .filter_map(|record| match record {
+            TES3Object::GlobalVariable(global) => Some(global.id.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    globals.sort();
+    globals.dedup();
+    globals
+}
+
+struct Validator<'a> {
+    text: &'a str,
+    locals: Vec<String>,
+    globals: &'a [String],
+    errors: Vec<ScriptError>,
+}
+
+impl Validator<'_> {
+    fn word(&self, token: &Token) -> &str {
+        &self.text[token.range.clone()]
+    }
+
+    fn error(&mut self, line: usize, message: String) {
+        self.errors.push(ScriptError { line, message });
+    }
+
+    fn is_variable(&self, name: &str) -> bool {
+        self.locals.iter().any(|l| l.eq_ignore_ascii_case(name))
+            || self.globals.iter().any(|g| g.eq_ignore_ascii_case(name))
+            || ENGINE_GLOBALS.iter().any(|g| g.eq_ignore_ascii_case(name))
+    }
+
+    /// Checks the variables and functions of an expression
+    fn check_expression(&mut self, line: usize, tokens: &[&Token]) {
+        let mut i = 0;
+        while let Some(token) = tokens.get(i) {
+            i += 1;
+            let word = self.word(token).to_owned();
+            let next = tokens.get(i).map(|t| self.word(t));
+            match token.kind {
+                // explicit references and remote variables
+                ETokenKind::Identifier | ETokenKind::String | ETokenKind::Function
+                    if next == Some("->") =>
+                {
+                    i += 1;
+                    if let Some(function) = tokens.get(i) {
+                        if function.kind == ETokenKind::Identifier {
+                            let name = self.word(function).to_owned();
+                            self.error(line, format!("unknown function '{}'", name));
+                            i += 1;
+                        }
+                    }
+                }
+                ETokenKind::Identifier | ETokenKind::String if next == Some(".") => {
+                    i += 2;
+                }
+                // arguments of functions
+                ETokenKind::Function => {
+                    while let Some(argument) = tokens.get(i) {
+                        let is_argument = match argument.kind {
+                            ETokenKind::Identifier | ETokenKind::String | ETokenKind::Number => {
+                                true
+                            }
+                            ETokenKind::Operator => self.word(argument) == ",",
+                            _ => false,
+                        };
+                        if !is_argument {
+                            break;
+                        }
+                        i += 1;
+                    }
+                }
+                ETokenKind::Identifier if !self.is_variable(&word) => {
+                    self.error(line, format!("undeclared variable '{}'", word));
+                }
+                ETokenKind::Keyword => {
+                    self.error(line, format!("unexpected '{}'", word));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks a function call statement
+    fn check_statement(&mut self, line: usize, tokens: &[&Token]) {
+        let mut head = 0;
+        if tokens.get(1).is_some_and(|t| self.word(t) == "->") {
+            head = 2;
+        }
+        let Some(function) = tokens.get(head) else {
+            self.error(line, "missing function after '->'".into());
+            return;
+        };
+        let word = self.word(function).to_owned();
+        match function.kind {
+            ETokenKind::Function => {}
+            ETokenKind::Identifier => self.error(line, format!("unknown function '{}'", word)),
+            _ => self.error(line, format!("unexpected '{}'", word)),
+        }
+    }
+
+    /// Checks that the brackets of a line are balanced
+    fn check_brackets(&mut self, line: usize, tokens: &[&Token]) {
+        let mut depth = 0;
+        for token in tokens.iter().filter(|t| t.kind == ETokenKind::Bracket) {
+            depth += if self.word(token) == "(" { 1 } else { -1 };
+            if depth < 0 {
+                break;
+            }
+        }
+        if depth != 0 {
+            self.error(line, "unbalanced brackets".into());
+        }
+    }
+}
+
+/// Validates a script and reports unknown functions, undeclared variables and unbalanced blocks
+/// Globals are the ids of the global variables the script may use
+pub fn validate_script(text: &str, globals: &[String]) -> Vec<ScriptError> {
+    let tokens = tokenize(text);
+    let lines = get_lines(&tokens);
+    let mut validator = Validator {
+        text,
+        locals: vec![],
+        globals,
+        errors: vec![],
+    };
+
+    // local variables may be used before they are declared
+    for (line, line_tokens) in lines.iter() {
+        let keyword = validator.word(line_tokens[0]).to_lowercase();
+        if !matches!(keyword.as_str(), "short" | "long" | "float") {
+            continue;
+        }
+        match line_tokens.get(1) {
+            Some(name) if name.kind != ETokenKind::Keyword => {
+                let name = validator.word(name).to_owned();
+                if validator
+                    .locals
+                    .iter()
+                    .any(|l| l.eq_ignore_ascii_case(&name))
+                {
+                    validator.error(*line, format!("'{}' is declared twice", name));
+                }
+                validator.locals.push(name);
+            }
+            _ => validator.error(*line, "missing variable name".into()),
+        }
+    }
+
+    let mut begin = None;
+    let mut end = None;
+    let mut blocks: Vec<(String, usize)> = vec![];
+    for (line, line_tokens) in lines.iter() {
+        let line = *line;
+        validator.check_brackets(line, line_tokens);
+        if end.is_some() {
+            validator.error(line, "code after 'end'".into());
+            break;
+        }
+
+        let first = line_tokens[0];
+        let keyword = validator.word(first).to_lowercase();
+        if first.kind != ETokenKind::Keyword {
+            if begin.is_none() {
+                validator.error(line, "code before 'begin'".into());
+            }
+            validator.check_statement(line, line_tokens);
+            continue;
+        }
+
+        match keyword.as_str() {
+            "begin" => {
+                if begin.is_some() {
+                    validator.error(line, "second 'begin'".into());
+                } else if line_tokens.len() < 2 {
+                    validator.error(line, "missing script name".into());
+                }
+                begin = Some(line);
+            }
+            "end" => {
+                for (block, block_line) in blocks.drain(..) {
+                    validator.error(block_line, format!("'{}' without 'end{}'", block, block));
+                }
+                end = Some(line);
+            }
+            "if" | "while" => {
+                blocks.push((keyword.clone(), line));
+                validator.check_expression(line, &line_tokens[1..]);
+            }
+            "elseif" | "else" => {
+                if !matches!(blocks.last(), Some((block, _)) if block == "if") {
+                    validator.error(line, format!("'{}' without 'if'", keyword));
+                }
+                validator.check_expression(line, &line_tokens[1..]);
+            }
+            "endif" | "endwhile" => {
+                let block = &keyword[3..];
+                if blocks.last().is_some_and(|(b, _)| b == block) {
+                    blocks.pop();
+                } else {
+                    validator.error(line, format!("'{}' without '{}'", keyword, block));
+                }
+            }
+            "set" => {
+                let Some(to) = line_tokens
+                    .iter()
+                    .position(|t| validator.word(t).eq_ignore_ascii_case("to"))
+                else {
+                    validator.error(line, "missing 'to'".into());
+                    continue;
+                };
+                match &line_tokens[1..to] {
+                    [] => validator.error(line, "missing variable to set".into()),
+                    [variable] => {
+                        let name = validator.word(variable).to_owned();
+                        if !validator.is_variable(&name) {
+                            validator.error(line, format!("undeclared variable '{}'", name));
+                        }
+                    }
+                    // remote variables
+                    _ => {}
+                }
+                if to + 1 >= line_tokens.len() {
+                    validator.error(line, "missing value after 'to'".into());
+                }
+                validator.check_expression(line, &line_tokens[to + 1..]);
+            }
+            "short" | "long" | "float" | "return" => {}
+            _ => validator.error(line, format!("unexpected '{}'", keyword)),
+        }
+    }
+
+    let last_line = text.lines().count().saturating_sub(1);
+    if begin.is_none() {
+        validator.error(0, "missing 'begin'".into());
+    } else if end.is_none() {
+        for (block, block_line) in blocks.drain(..) {
+            validator.error(block_line, format!("'{}' without 'end{}'", block, block));
+        }
+        validator.error(last_line, "missing 'end'".into());
+    }
+
+    validator.errors.sort_by_key(|e| e.line);
+    validator.errors
+}
+
+/// Returns the lines of the innermost if or while block that contains the line
+/// The lines are the ones with the block keywords, e.g. if, elseif, else and endif
+pub fn get_block_lines(text: &str, line: usize) -> Vec<usize> {
+    let tokens = tokenize(text);
+    let mut open: Vec<(String, Vec<usize>)> = vec![];
+    for (token_line, line_tokens) in get_lines(&tokens) {
+        let keyword = text[line_tokens[0].range.clone()].to_lowercase();
+        match keyword.as_str() {
+            "if" | "while" => open.push((keyword, vec![token_line])),
+            "elseif" | "else" => {
+                if let Some((_, block)) = open.last_mut().filter(|(b, _)| b == "if") {
+                    block.push(token_line);
+                }
+            }
+            "endif" | "endwhile" if open.last().is_some_and(|(b, _)| *b == keyword[3..]) => {
+                let (_, mut block) = open.pop().unwrap_or_default();
+                block.push(token_line);
+                if block.contains(&line) {
+                    return block;
+                }
+            }
+            _ => {}
+        }
+    }
+    vec![]
+}
+
+/// Returns the range of the bracket that matches the bracket at or before the byte offset
+pub fn get_matching_bracket(text: &str, offset: usize) -> Option<(Range<usize>, Range<usize>)> {
+    let tokens = tokenize(text)
+        .into_iter()
+        .filter(|t| t.kind == ETokenKind::Bracket)
+        .collect::<Vec<_>>();
+    let index = tokens
+        .iter()
+        .position(|t| t.range.start == offset)
+        .or_else(|| tokens.iter().position(|t| t.range.end == offset))?;
+
+    let is_open = |t: &Token| &text[t.range.clone()] == "(";
+    let mut depth = 0;
+    if is_open(&tokens[index]) {
+        for token in tokens[index..].iter() {
+            depth += if is_open(token) { 1 } else { -1 };
+            if depth == 0 {
+                return Some((tokens[index].range.clone(), token.range.clone()));
+            }
+        }
+    } else {
+        for token in tokens[..=index].iter().rev() {
+            depth += if is_open(token) { -1 } else { 1 };
+            if depth == 0 {
+                return Some((token.range.clone(), tokens[index].range.clone()));
+            }
+        }
+    }
+    None
+}
+
+/// Returns the ranges to mark for the cursor: a matching bracket pair or the keywords of a block
+pub fn get_cursor_matches(text: &str, offset: usize) -> Vec<Range<usize>> {
+    // the offset may be from before the text changed
+    if !text.is_char_boundary(offset) {
+        return vec![];
+    }
+    if let Some((open, close)) = get_matching_bracket(text, offset) {
+        return vec![open, close];
+    }
+
+    let line = text[..offset].matches('\n').count();
+    let block = get_block_lines(text, line);
+    tokenize(text)
+        .into_iter()
+        .filter(|t| t.kind == ETokenKind::Keyword && block.contains(&t.line))
+        .filter(|t| {
+            let word = text[t.range.clone()].to_lowercase();
+            matches!(
+                word.as_str(),
+                "if" | "elseif" | "else" | "endif" | "while" | "endwhile"
+            )
+        })
+        .map(|t| t.range)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effect_functions() {
+        let text = "Begin test\nif ( GetResistFire > 50 )\n    ModSilence 1\n    SetWaterBreathing 1\nendif\nEnd test\n";
+        let errors = validate_script(text, &[]);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn cursor_inside_character() {
+        let text = "Begin test\n; schön\nif ( 1 )\nendif\nEnd test\n";
+        let inside = text.find('ö').unwrap() + 1;
+        assert!(get_cursor_matches(text, inside).is_empty());
+        assert!(get_cursor_matches(text, text.len() + 10).is_empty());
+
+        let offset = text.find("if").unwrap();
+        assert_eq!(get_cursor_matches(text, offset).len(), 2);
+    }
+}
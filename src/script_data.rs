@@ -0,0 +1,388 @@
+use std::fmt::Display;
+
+use egui_notify::Toasts;
+use tes3::esp::{Script, TES3Object};
+
+use crate::{
+    get_unique_id,
+    script::{get_lines, tokenize, ETokenKind},
+    PluginMetadata,
+};
+
+/// The variable declarations of a script text with their line and type
+fn get_declarations(text: &str) -> Vec<(usize, &'static str, String)> {
+    let tokens = tokenize(text);
+    let mut declarations = vec![];
+    for (line, line_tokens) in get_lines(&tokens) {
+        let Some(name) = line_tokens.get(1).map(|t| text[t.range.clone()].to_owned()) else {
+            continue;
+        };
+        let keyword = match text[line_tokens[0].range.clone()].to_lowercase().as_str() {
+            "short" => "short",
+            "long" => "long",
+            "float" => "float",
+            _ => continue,
+        };
+        declarations.push((line, keyword, name));
+    }
+    declarations
+}
+
+/// Names of the local variables of a script
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Variables {
+    pub shorts: Vec<String>,
+    pub longs: Vec<String>,
+    pub floats: Vec<String>,
+}
+
+impl Variables {
+    /// Collects the declared variables of a script text
+    pub fn from_text(text: &str) -> Self {
+        let mut variables = Variables::default();
+        for (_, keyword, name) in get_declarations(text) {
+            match keyword {
+                "short" => variables.shorts.push(name),
+                "long" => variables.longs.push(name),
+                _ => variables.floats.push(name),
+            }
+        }
+        variables
+    }
+
+    /// Reads the variable table of a compiled script
+    pub fn from_script(script: &Script) -> Self {
+        let mut names = script
+            .variables
+            .split(|b| *b == 0)
+            .map(|name| String::from_utf8_lossy(name).to_string());
+        let mut take = |count: u32| names.by_ref().take(count as usize).collect::<Vec<_>>();
+        Variables {
+            shorts: take(script.header.num_shorts),
+            longs: take(script.header.num_longs),
+            floats: take(script.header.num_floats),
+        }
+    }
+
+    /// Null terminated names, shorts first, then longs and floats
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for name in self
+            .shorts
+            .iter()
+            .chain(self.longs.iter())
+            .chain(self.floats.iter())
+        {
+            bytes.extend(name.as_bytes());
+            bytes.push(0);
+        }
+        bytes
+    }
+}
+
+/// Minimum length of a run of printable bytes in the bytecode to be shown as text
+const MIN_TEXT_LENGTH: usize = 3;
+
+/// Runs of printable text in the bytecode with their byte offset
+/// Message texts, object ids and global names are stored as text in the compiled data
+pub fn get_bytecode_texts(bytecode: &[u8]) -> Vec<(usize, String)> {
+    let mut texts = vec![];
+    let mut start = 0;
+    for (i, byte) in bytecode.iter().chain(std::iter::once(&0)).enumerate() {
+        if (0x20..0x7f).contains(byte) {
+            continue;
+        }
+        if i - start >= MIN_TEXT_LENGTH {
+            texts.push((
+                start,
+                String::from_utf8_lossy(&bytecode[start..i]).to_string(),
+            ));
+        }
+        start = i + 1;
+    }
+    texts
+}
+
+/// Lists what can be read from the compiled data without decoding the statements:
+/// the variable table and the texts of the bytecode by byte offset
+pub fn format_compiled_data(script: &Script) -> String {
+    let variables = Variables::from_script(script);
+    let mut lines = vec![format!("; compiled data of {}", script.id), String::new()];
+    for (keyword, names) in [
+        ("short", &variables.shorts),
+        ("long", &variables.longs),
+        ("float", &variables.floats),
+    ] {
+        lines.extend(names.iter().map(|name| format!("{} {}", keyword, name)));
+    }
+    if lines.len() > 2 {
+        lines.push(String::new());
+    }
+
+    lines.push(format!(
+        "; {} bytes of compiled statements, not decoded, texts by byte offset:",
+        script.bytecode.len()
+    ));
+    for (offset, text) in get_bytecode_texts(&script.bytecode) {
+        lines.push(format!("; {:04X}: {}", offset, text));
+    }
+    lines.join("\n")
+}
+
+/// A place where the stored text and the compiled data of a script disagree
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptMismatch {
+    /// zero based line of the text, None if the mismatch is not on a line
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Display for ScriptMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "Line {}: {}", line + 1, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Compares the stored text of a script with its compiled data
+/// Declarations are checked against the variable table and string literals against the bytecode,
+/// changed conditions, numbers or function calls are not found since the statements are not decoded
+pub fn get_script_mismatches(script: &Script) -> Vec<ScriptMismatch> {
+    let mut mismatches = vec![];
+    let mut add = |line: Option<usize>, message: String| {
+        mismatches.push(ScriptMismatch { line, message });
+    };
+    if script.header.bytecode_length as usize != script.bytecode.len() {
+        add(
+            None,
+            "The header bytecode length does not match the bytecode".into(),
+        );
+    }
+    if script.header.variables_length as usize != script.variables.len() {
+        add(
+            None,
+            "The header variables length does not match the variables".into(),
+        );
+    }
+    if script.text.trim().is_empty() {
+        add(None, "The source text is missing".into());
+        return mismatches;
+    }
+
+    // the bytecode refers to locals by type and position
+    let compiled = Variables::from_script(script);
+    let declarations = get_declarations(&script.text);
+    for (line, keyword, name) in declarations.iter() {
+        let position = |names: &[String]| {
+            names
+                .iter()
+                .position(|n| n.eq_ignore_ascii_case(name))
+                .map(|i| i + 1)
+        };
+        let declared = declarations
+            .iter()
+            .filter(|(_, k, _)| k == keyword)
+            .position(|(l, _, _)| l == line)
+            .map(|i| i + 1);
+        let found = [
+            ("short", position(&compiled.shorts)),
+            ("long", position(&compiled.longs)),
+            ("float", position(&compiled.floats)),
+        ]
+        .into_iter()
+        .find_map(|(k, p)| p.map(|p| (k, p)));
+        match found {
+            None => add(
+                Some(*line),
+                format!("{} is not in the compiled variable table", name),
+            ),
+            Some((k, _)) if k != *keyword => add(
+                Some(*line),
+                format!("{} is declared as {}, but compiled as {}", name, keyword, k),
+            ),
+            Some((_, p)) if Some(p) != declared => add(
+                Some(*line),
+                format!(
+                    "{} is {} {} in the text, but {} {} in the compiled data",
+                    name,
+                    keyword,
+                    declared.unwrap_or_default(),
+                    keyword,
+                    p
+                ),
+            ),
+            _ => {}
+        }
+    }
+    for name in compiled
+        .shorts
+        .iter()
+        .chain(compiled.longs.iter())
+        .chain(compiled.floats.iter())
+    {
+        if !declarations
+            .iter()
+            .any(|(_, _, n)| n.eq_ignore_ascii_case(name))
+        {
+            add(
+                None,
+                format!("Compiled variable {} is not declared in the text", name),
+            );
+        }
+    }
+
+    // texts are stored as they were written
+    let bytecode = script.bytecode.to_ascii_lowercase();
+    let tokens = tokenize(&script.text);
+    for token in tokens.iter().filter(|t| t.kind == ETokenKind::String) {
+        let text = script.text[token.range.clone()].trim_matches('"');
+        if text.is_empty() {
+            continue;
+        }
+        let needle = text.as_bytes().to_ascii_lowercase();
+        if !bytecode.windows(needle.len()).any(|w| w == needle) {
+            add(
+                Some(token.line),
+                format!("\"{}\" is not in the compiled data", text),
+            );
+        }
+    }
+    mismatches
+}
+
+/// Updates the compiled data of the edited scripts among the records to save
+/// Only the variable table of scripts without bytecode and the header lengths are updated.
+/// There is no script compiler: the bytecode of changed and new scripts stays as it is
+/// and has to be compiled by the Construction Set, until then the game runs the old bytecode
+/// or nothing for new scripts
+pub fn check_edited_scripts(
+    data: &PluginMetadata,
+    records: &mut [TES3Object],
+    toasts: &mut Toasts,
+) {
+    let mut stale = vec![];
+    for record in records.iter_mut() {
+        let id = get_unique_id(record);
+        if !data.edited_records.contains_key(&id) {
+            continue;
+        }
+        let TES3Object::Script(script) = record else {
+            continue;
+        };
+
+        // without bytecode the variables can't be out of order
+        if script.bytecode.is_empty() {
+            let variables = Variables::from_text(&script.text);
+            script.header.num_shorts = variables.shorts.len() as u32;
+            script.header.num_longs = variables.longs.len() as u32;
+            script.header.num_floats = variables.floats.len() as u32;
+            script.variables = variables.to_bytes();
+            stale.push(script.id.clone());
+        } else if !matches!(
+            data.records.get(&id),
+            Some(TES3Object::Script(original)) if original.text == script.text
+        ) {
+            stale.push(script.id.clone());
+        }
+        script.header.variables_length = script.variables.len() as u32;
+        script.header.bytecode_length = script.bytecode.len() as u32;
+    }
+
+    if !stale.is_empty() {
+        toasts.warning(format!(
+            "The compiled data of {} edited scripts is not up to date, compile them in the Construction Set: {}",
+            stale.len(),
+            stale.join(", ")
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tes3::esp::ScriptHeader;
+
+    use super::*;
+
+    /// A script whose compiled data has the variables and texts of the source
+    fn script(text: &str, compiled_text: &str, bytecode: &[u8]) -> Script {
+        let variables = Variables::from_text(compiled_text).to_bytes();
+        let compiled = Variables::from_text(compiled_text);
+        Script {
+            id: "test".into(),
+            header: ScriptHeader {
+                num_shorts: compiled.shorts.len() as u32,
+                num_longs: compiled.longs.len() as u32,
+                num_floats: compiled.floats.len() as u32,
+                bytecode_length: bytecode.len() as u32,
+                variables_length: variables.len() as u32,
+            },
+            variables,
+            bytecode: bytecode.to_vec(),
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    fn lines(mismatches: &[ScriptMismatch]) -> Vec<Option<usize>> {
+        mismatches.iter().map(|m| m.line).collect()
+    }
+
+    const TEXT: &str = "Begin test\nshort state\nfloat timer\nMessageBox \"Hello\"\nEnd test\n";
+
+    #[test]
+    fn matching_script() {
+        let script = script(TEXT, TEXT, b"\x01\x10\x05Hello\x00\x01\x01");
+        assert_eq!(get_script_mismatches(&script), vec![]);
+    }
+
+    #[test]
+    fn variables() {
+        // a declaration that is not compiled, one with another type and a reordered one
+        let text = "Begin test\nshort state\nshort other\nlong count\nfloat timer\nEnd test\n";
+        let compiled = "short other\nshort state\nfloat count\n";
+        let mismatches = get_script_mismatches(&script(text, compiled, b""));
+        assert_eq!(lines(&mismatches), vec![Some(1), Some(2), Some(3), Some(4)]);
+        assert_eq!(
+            mismatches[2].message,
+            "count is declared as long, but compiled as float"
+        );
+        assert_eq!(
+            mismatches[3].message,
+            "timer is not in the compiled variable table"
+        );
+
+        // a compiled variable that is not declared
+        let mismatches = get_script_mismatches(&script(
+            TEXT,
+            "short state\nfloat timer\nlong x\n",
+            b"Hello",
+        ));
+        assert_eq!(lines(&mismatches), vec![None]);
+    }
+
+    #[test]
+    fn texts() {
+        let script = script(TEXT, TEXT, b"\x01\x10\x07Goodbye");
+        let mismatches = get_script_mismatches(&script);
+        assert_eq!(lines(&mismatches), vec![Some(3)]);
+    }
+
+    #[test]
+    fn header_and_missing_text() {
+        let mut script = script("", TEXT, b"Hello");
+        script.header.bytecode_length = 1;
+        let mismatches = get_script_mismatches(&script);
+        assert_eq!(lines(&mismatches), vec![None, None]);
+    }
+
+    #[test]
+    fn bytecode_texts() {
+        let texts = get_bytecode_texts(b"\x01\x10\x05Hello\x00ab\x02fargoth");
+        assert_eq!(
+            texts,
+            vec![(3, "Hello".to_owned()), (12, "fargoth".to_owned())]
+        );
+    }
+}
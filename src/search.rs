@@ -0,0 +1,134 @@
+use std::{fmt::Display, ops::Range};
+
+use regex::{Regex, RegexBuilder};
+use tes3::esp::TES3Object;
+
+use crate::{get_unique_id, PluginMetadata};
+
+/// The text fields that are searched
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ETextField {
+    Script,
+    Response,
+    Result,
+}
+
+impl Display for ETextField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ETextField::Script => write!(f, "Script"),
+            ETextField::Response => write!(f, "Response"),
+            ETextField::Result => write!(f, "Result"),
+        }
+    }
+}
+
+/// A line that matches the search
+#[derive(Debug, Clone)]
+pub struct TextMatch {
+    pub plugin_id: String,
+    pub record_id: String,
+    pub field: ETextField,
+    /// zero based line in the field
+    pub line: usize,
+    pub text: String,
+    /// byte range of the first match in the line
+    pub range: Range<usize>,
+    /// the line with the lines before and after it
+    pub context: String,
+}
+
+/// Builds the search regex, plain queries are escaped
+pub fn build_search_regex(
+    query: &str,
+    use_regex: bool,
+    whole_word: bool,
+    case_sensitive: bool,
+) -> Result<Regex, regex::Error> {
+    let mut pattern = if use_regex {
+        query.to_owned()
+    } else {
+        regex::escape(query)
+    };
+    if whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+}
+
+fn search_field(
+    matches: &mut Vec<TextMatch>,
+    regex: &Regex,
+    plugin_id: &str,
+    record_id: &str,
+    field: ETextField,
+    text: &str,
+) {
+    let lines = text.lines().collect::<Vec<_>>();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(found) = regex.find(line) else {
+            continue;
+        };
+        let context = lines[index.saturating_sub(1)..(index + 2).min(lines.len())].join("\n");
+        matches.push(TextMatch {
+            plugin_id: plugin_id.to_owned(),
+            record_id: record_id.to_owned(),
+            field,
+            line: index,
+            text: line.to_string(),
+            range: found.start()..found.end(),
+            context,
+        });
+    }
+}
+
+/// Searches script text, dialogue responses and dialogue results of all open plugins
+/// Edited records are searched instead of their original
+pub fn search_text(plugins: &[PluginMetadata], regex: &Regex) -> Vec<TextMatch> {
+    let mut matches = vec![];
+    for plugin in plugins {
+        let mut records = plugin
+            .records
+            .values()
+            .filter(|r| !plugin.edited_records.contains_key(&get_unique_id(r)))
+            .chain(plugin.edited_records.values())
+            .collect::<Vec<_>>();
+        records.sort_by_key(|r| get_unique_id(r));
+
+        for record in records {
+            let record_id = get_unique_id(record);
+            match record {
+                TES3Object::Script(script) => search_field(
+                    &mut matches,
+                    regex,
+                    &plugin.id,
+                    &record_id,
+                    ETextField::Script,
+                    &script.text,
+                ),
+                TES3Object::DialogueInfo(info) => {
+                    search_field(
+                        &mut matches,
+                        regex,
+                        &plugin.id,
+                        &record_id,
+                        ETextField::Response,
+                        &info.text,
+                    );
+                    search_field(
+                        &mut matches,
+                        regex,
+                        &plugin.id,
+                        &record_id,
+                        ETextField::Result,
+                        &info.script_text,
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+    matches
+}
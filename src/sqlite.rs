@@ -0,0 +1,298 @@
+use std::{collections::HashMap, path::Path};
+
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+use tes3::esp::{EditorId, TES3Object, TypeInfo};
+
+use crate::{clean::get_reference_origin, get_record_fields, get_unique_id, record_to_json};
+
+/// Records with more fields than this only get the json column, sqlite allows 2000 columns
+const MAX_FIELD_COLUMNS: usize = 1000;
+
+/// Quotes a table or column name
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Converts a json field value to a sql value, arrays and objects stay json text
+fn to_sql_value(json: &str) -> Value {
+    match serde_json::from_str::<serde_json::Value>(json) {
+        Ok(serde_json::Value::Null) => Value::Null,
+        Ok(serde_json::Value::Bool(b)) => Value::Integer(b as i64),
+        Ok(serde_json::Value::Number(n)) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        Ok(serde_json::Value::String(s)) => Value::Text(s),
+        _ => Value::Text(json.to_owned()),
+    }
+}
+
+/// Names of the masters in the header of a plugin
+fn get_master_names(records: &[&TES3Object]) -> Vec<String> {
+    records
+        .iter()
+        .find_map(|record| match record {
+            TES3Object::Header(header) => Some(
+                header
+                    .masters
+                    .iter()
+                    .map(|(name, _)| name.to_owned())
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Writes plugins in load order into a new sqlite database
+///
+/// Tables:
+/// - `plugins`: name and load order
+/// - `overrides`: every record of every plugin, and if it is the winning version
+/// - `refs`: the references of all cells with the lowercase name of the plugin that created them
+/// - one table per record tag with a column per field path and the record as json
+pub fn export_sqlite(path: &Path, plugins: &[(String, Vec<&TES3Object>)]) -> Result<(), String> {
+    // always start from an empty database
+    if path.exists() {
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    write_database(path, plugins).map_err(|e| e.to_string())
+}
+
+fn write_database(path: &Path, plugins: &[(String, Vec<&TES3Object>)]) -> rusqlite::Result<()> {
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+
+    transaction.execute_batch(
+        "CREATE TABLE plugins (load_order INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE overrides (
+            plugin TEXT NOT NULL,
+            load_order INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            id TEXT NOT NULL,
+            is_winner INTEGER NOT NULL
+        );
+        CREATE TABLE refs (
+            plugin TEXT NOT NULL,
+            cell TEXT NOT NULL,
+            mast_index INTEGER NOT NULL,
+            refr_index INTEGER NOT NULL,
+            origin_plugin TEXT,
+            id TEXT NOT NULL,
+            x REAL, y REAL, z REAL,
+            rot_x REAL, rot_y REAL, rot_z REAL,
+            scale REAL,
+            deleted INTEGER NOT NULL
+        );",
+    )?;
+
+    // the last plugin that has a record wins
+    let mut winners: HashMap<String, usize> = HashMap::new();
+    for (load_order, (_, records)) in plugins.iter().enumerate() {
+        for record in records {
+            winners.insert(get_unique_id(record), load_order);
+        }
+    }
+
+    // records per tag with their plugin
+    let mut tags: HashMap<&str, Vec<(&str, &TES3Object)>> = HashMap::new();
+    {
+        let mut insert_plugin =
+            transaction.prepare("INSERT INTO plugins (load_order, name) VALUES (?1, ?2)")?;
+        let mut insert_override = transaction.prepare(
+            "INSERT INTO overrides (plugin, load_order, tag, id, is_winner) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        let mut insert_ref = transaction.prepare(
+            "INSERT INTO refs VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        )?;
+
+        for (load_order, (name, records)) in plugins.iter().enumerate() {
+            insert_plugin.execute(params![load_order, name])?;
+            let master_names = get_master_names(records);
+            for record in records {
+                let id = get_unique_id(record);
+                let is_winner = winners.get(&id) == Some(&load_order);
+                insert_override.execute(params![
+                    name,
+                    load_order,
+                    record.tag_str(),
+                    record.editor_id(),
+                    is_winner
+                ])?;
+                tags.entry(record.tag_str())
+                    .or_default()
+                    .push((name.as_str(), record));
+
+                if let TES3Object::Cell(cell) = record {
+                    let mut keys = cell.references.keys().collect::<Vec<_>>();
+                    keys.sort();
+                    for key in keys {
+                        let reference = &cell.references[key];
+                        // null when the master is missing from the header
+                        let origin = get_reference_origin(*key, name, &master_names)
+                            .map(|(plugin, _)| plugin);
+                        insert_ref.execute(params![
+                            name,
+                            id,
+                            reference.mast_index,
+                            reference.refr_index,
+                            origin,
+                            reference.id,
+                            reference.translation[0],
+                            reference.translation[1],
+                            reference.translation[2],
+                            reference.rotation[0],
+                            reference.rotation[1],
+                            reference.rotation[2],
+                            reference.scale,
+                            reference.deleted.is_some()
+                        ])?;
+                    }
+                }
+            }
+        }
+    }
+
+    for (tag, records) in tags {
+        let fields = records
+            .iter()
+            .map(|(_, record)| get_record_fields(record))
+            .collect::<Vec<_>>();
+
+        // all field paths in the order they first appear
+        let mut columns: Vec<&str> = vec![];
+        for record_fields in fields.iter() {
+            for (name, _) in record_fields {
+                if !columns.contains(&name.as_str()) {
+                    columns.push(name);
+                }
+            }
+        }
+        if columns.len() > MAX_FIELD_COLUMNS {
+            columns.clear();
+        }
+        let columns = columns
+            .into_iter()
+            .filter(|c| !["plugin", "record_id", "json"].contains(c))
+            .collect::<Vec<_>>();
+
+        let definitions = columns
+            .iter()
+            .map(|c| format!(", {}", quote(c)))
+            .collect::<String>();
+        transaction.execute_batch(&format!(
+            "CREATE TABLE {} (plugin TEXT NOT NULL, record_id TEXT NOT NULL, json TEXT{});",
+            quote(tag),
+            definitions
+        ))?;
+
+        let placeholders = (0..columns.len() + 3)
+            .map(|i| format!("?{}", i + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let names = columns.iter().map(|c| quote(c)).collect::<Vec<_>>();
+        let mut insert = transaction.prepare(&format!(
+            "INSERT INTO {} (plugin, record_id, json{}) VALUES ({})",
+            quote(tag),
+            names.iter().map(|n| format!(", {}", n)).collect::<String>(),
+            placeholders
+        ))?;
+
+        for ((plugin, record), record_fields) in records.iter().zip(fields.iter()) {
+            let json =
+                record_to_json(record).map_or(Value::Null, |json| Value::Text(json.to_string()));
+            let mut values = vec![
+                Value::Text(plugin.to_string()),
+                Value::Text(record.editor_id().to_string()),
+                json,
+            ];
+            values.extend(columns.iter().map(|column| {
+                record_fields
+                    .iter()
+                    .find(|(name, _)| name == column)
+                    .map(|(_, value)| to_sql_value(value))
+                    .unwrap_or(Value::Null)
+            }));
+            insert.execute(params_from_iter(values))?;
+        }
+    }
+
+    transaction.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use tes3::esp::{Cell, Header, Reference};
+
+    use super::*;
+    use crate::test_utils::{self, reference};
+
+    fn cell(references: Vec<Reference>) -> TES3Object {
+        TES3Object::Cell(Cell {
+            name: "Balmora".into(),
+            ..test_utils::cell(references)
+        })
+    }
+
+    #[test]
+    fn export_references() {
+        let header = TES3Object::Header(Header {
+            masters: vec![("Morrowind.esm".into(), 0)],
+            ..Default::default()
+        });
+        let master_cell = cell(vec![reference(0, 1, "lamp")]);
+        let plugin_cell = cell(vec![
+            reference(1, 1, "lamp"),
+            reference(0, 1, "rug"),
+            reference(2, 1, "chair"),
+        ]);
+        let plugins = vec![
+            ("Morrowind.esm".to_string(), vec![&master_cell]),
+            ("a.esp".to_string(), vec![&header, &plugin_cell]),
+        ];
+
+        let path = std::env::temp_dir().join(format!("tes3edit_{}.sqlite", std::process::id()));
+        export_sqlite(&path, &plugins).unwrap();
+        let connection = Connection::open(&path).unwrap();
+
+        let mut statement = connection
+            .prepare(
+                "SELECT mast_index, refr_index, origin_plugin, id FROM refs \
+                WHERE plugin = 'a.esp' ORDER BY mast_index, refr_index",
+            )
+            .unwrap();
+        let refs = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, u32>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            refs,
+            vec![
+                (0, 1, Some("a.esp".to_string()), "rug".to_string()),
+                (1, 1, Some("morrowind.esm".to_string()), "lamp".to_string()),
+                (2, 1, None, "chair".to_string()),
+            ]
+        );
+
+        // cells are written as json with their references as a list
+        let json: String = connection
+            .query_row("SELECT json FROM CELL WHERE plugin = 'a.esp'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["references"].as_array().unwrap().len(), 3);
+
+        drop(statement);
+        connection.close().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}
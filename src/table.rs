@@ -0,0 +1,240 @@
+use std::cmp::Ordering;
+
+use serde_json::Value;
+use tes3::esp::TypeInfo;
+
+use crate::{get_record_fields, get_unique_id, set_field_value, PluginMetadata};
+
+/// A record in the table view
+#[derive(Debug, Clone)]
+pub struct TableRow {
+    /// unique id of the record
+    pub id: String,
+    /// the cell texts, strings without quotes
+    pub values: Vec<String>,
+    /// the json values of the fields the cells were created from
+    pub json: Vec<String>,
+}
+
+/// Formats a json field value for a cell, strings are shown without quotes
+pub fn format_cell(json: &str) -> String {
+    match serde_json::from_str::<Value>(json) {
+        Ok(Value::String(text)) => text,
+        _ => json.to_owned(),
+    }
+}
+
+/// Parses the text of a cell as the same kind of json value as the field
+pub fn parse_cell(json: &str, text: &str) -> Result<Value, String> {
+    match serde_json::from_str::<Value>(json) {
+        Ok(Value::String(_)) => Ok(Value::String(text.to_owned())),
+        _ => serde_json::from_str(text).map_err(|e| format!("invalid value '{}': {}", text, e)),
+    }
+}
+
+/// Creates a table row of a record with the given columns
+pub fn get_table_row(id: &str, fields: &[(String, String)], columns: &[String]) -> TableRow {
+    let json = columns
+        .iter()
+        .map(|column| {
+            fields
+                .iter()
+                .find(|(name, _)| name == column)
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    TableRow {
+        id: id.to_owned(),
+        values: json.iter().map(|j| format_cell(j)).collect(),
+        json,
+    }
+}
+
+/// Creates the columns and rows of all records of a type, edited records replace their original
+pub fn get_table(plugin: &PluginMetadata, tag: &str) -> (Vec<String>, Vec<TableRow>) {
+    let mut records = plugin
+        .records
+        .iter()
+        .filter(|(id, _)| !plugin.edited_records.contains_key(*id))
+        .chain(plugin.edited_records.iter())
+        .filter(|(_, record)| record.tag_str() == tag)
+        .map(|(_, record)| (get_unique_id(record), get_record_fields(record)))
+        .collect::<Vec<_>>();
+    records.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // all field paths in the order they first appear
+    let mut columns: Vec<String> = vec![];
+    for (_, fields) in records.iter() {
+        for (name, _) in fields {
+            if !columns.contains(name) {
+                columns.push(name.clone());
+            }
+        }
+    }
+
+    let rows = records
+        .iter()
+        .map(|(id, fields)| get_table_row(id, fields, &columns))
+        .collect();
+    (columns, rows)
+}
+
+/// Compares cells as numbers if both are numbers, else as text
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// Indices of the rows that match the filter, in sort order
+pub fn get_visible_rows(
+    rows: &[TableRow],
+    filter: &str,
+    sort_column: Option<usize>,
+    ascending: bool,
+) -> Vec<usize> {
+    let filter = filter.to_lowercase();
+    let mut visible = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| {
+            filter.is_empty()
+                || row.id.to_lowercase().contains(&filter)
+                || row
+                    .values
+                    .iter()
+                    .any(|v| v.to_lowercase().contains(&filter))
+        })
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    if let Some(column) = sort_column {
+        visible.sort_by(|a, b| {
+            let ordering = compare_cells(&rows[*a].values[column], &rows[*b].values[column]);
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+    visible
+}
+
+/// Header of the record id column in csv files
+const CSV_ID_COLUMN: &str = "record";
+
+/// Writes rows as csv with one column per field path
+pub fn write_csv<'a>(
+    columns: &[String],
+    rows: impl Iterator<Item = &'a TableRow>,
+) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record(std::iter::once(CSV_ID_COLUMN).chain(columns.iter().map(|c| c.as_str())))
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        writer
+            .write_record(std::iter::once(&row.id).chain(row.values.iter()))
+            .map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// A cell of an imported csv that differs from the record
+#[derive(Debug, Clone)]
+pub struct CsvChange {
+    pub record_id: String,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+/// The changes of an imported csv, not yet applied
+#[derive(Debug, Clone, Default)]
+pub struct CsvImport {
+    pub changes: Vec<CsvChange>,
+    /// ids of the csv that are not in the plugin
+    pub unknown_records: Vec<String>,
+}
+
+/// Reads a csv and matches its rows by record id against the plugin
+/// Only cells that differ from the current value become changes
+pub fn read_csv(plugin: &PluginMetadata, text: &str) -> Result<CsvImport, String> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    if headers.get(0) != Some(CSV_ID_COLUMN) {
+        return Err(format!("the first column must be '{}'", CSV_ID_COLUMN));
+    }
+
+    let mut import = CsvImport::default();
+    for line in reader.records() {
+        let line = line.map_err(|e| e.to_string())?;
+        let record_id = line.get(0).unwrap_or_default().to_owned();
+        let Some(record) = plugin
+            .edited_records
+            .get(&record_id)
+            .or_else(|| plugin.records.get(&record_id))
+        else {
+            import.unknown_records.push(record_id);
+            continue;
+        };
+
+        let fields = get_record_fields(record);
+        for (field, new_value) in headers.iter().zip(line.iter()).skip(1) {
+            // the id is the key of the record
+            if field == "id" {
+                continue;
+            }
+            let Some((_, json)) = fields.iter().find(|(name, _)| name == field) else {
+                continue;
+            };
+            let old_value = format_cell(json);
+            if old_value != new_value {
+                import.changes.push(CsvChange {
+                    record_id: record_id.clone(),
+                    field: field.to_owned(),
+                    old_value,
+                    new_value: new_value.to_owned(),
+                });
+            }
+        }
+    }
+    Ok(import)
+}
+
+/// Applies imported changes as edited records, returns the errors of cells that could not be set
+pub fn apply_csv_changes(plugin: &mut PluginMetadata, changes: &[CsvChange]) -> Vec<String> {
+    let mut errors = vec![];
+    for change in changes {
+        let Some(record) = plugin
+            .edited_records
+            .get(&change.record_id)
+            .or_else(|| plugin.records.get(&change.record_id))
+        else {
+            continue;
+        };
+        let fields = get_record_fields(record);
+        let Some((_, json)) = fields.iter().find(|(name, _)| *name == change.field) else {
+            continue;
+        };
+
+        match parse_cell(json, &change.new_value)
+            .and_then(|value| set_field_value(record, &change.field, value))
+        {
+            Ok(edited) => {
+                plugin
+                    .edited_records
+                    .insert(change.record_id.clone(), edited);
+            }
+            Err(error) => {
+                errors.push(format!("{} {}: {}", change.record_id, change.field, error));
+            }
+        }
+    }
+    plugin.clear_cache();
+    errors
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use tes3::esp::{Cell, Reference};
+
+/// A reference with its key and object id
+pub fn reference(mast_index: u32, refr_index: u32, id: &str) -> Reference {
+    Reference {
+        mast_index,
+        refr_index,
+        id: id.into(),
+        ..Default::default()
+    }
+}
+
+/// A cell with the references keyed by their indices
+pub fn cell(references: Vec<Reference>) -> Cell {
+    Cell {
+        references: references
+            .into_iter()
+            .map(|r| ((r.mast_index, r.refr_index), r))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Keys and object ids of references sorted by key, the keys must match the references
+pub fn get_references(references: &HashMap<(u32, u32), Reference>) -> Vec<((u32, u32), String)> {
+    let mut list = references
+        .iter()
+        .map(|(key, r)| {
+            assert_eq!(*key, (r.mast_index, r.refr_index));
+            (*key, r.id.clone())
+        })
+        .collect::<Vec<_>>();
+    list.sort();
+    list
+}
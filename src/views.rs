@@ -1,8 +1,22 @@
+pub(crate) mod cell_layout_view;
 pub(crate) mod conflict_compare_view;
 pub(crate) mod conflict_list_view;
 pub(crate) mod conflict_menu_bar_view;
+pub(crate) mod dialogue_view;
 pub(crate) mod edit_menu_bar_view;
 pub(crate) mod edit_record_editor_view;
 pub(crate) mod edit_records_list_view;
+pub(crate) mod filters_view;
+pub(crate) mod loader_view;
+pub(crate) mod map_view;
 pub(crate) mod modal_compare_view;
+pub(crate) mod modal_heightmap;
 pub(crate) mod modal_settings;
+pub(crate) mod pathgrid_view;
+pub(crate) mod query_view;
+pub(crate) mod quest_view;
+pub(crate) mod records_chains_view;
+pub(crate) mod records_history_view;
+pub(crate) mod script_view;
+pub(crate) mod table_view;
+pub(crate) mod text_search_view;
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use egui::{Color32, Pos2, Rect, Response, Sense, Stroke, Vec2};
+use tes3::esp::{Cell, EditorId, TES3Object, TypeInfo};
+
+use crate::{LayoutData, PluginMetadata};
+
+/// Pick radius around a point in screen points
+pub(crate) const PICK_RADIUS: f32 = 8.0;
+/// Size of an exterior cell in game units
+pub(crate) const EXTERIOR_CELL_SIZE: f32 = 8192.0;
+
+/// Maps world positions of a cell to the screen
+pub(crate) struct LayoutTransform {
+    center: Pos2,
+    world_center: Vec2,
+    scale: f32,
+}
+
+impl LayoutTransform {
+    /// Fits the world bounds into the rect, then applies zoom and pan
+    pub(crate) fn new(rect: Rect, bounds: Rect, layout: &LayoutData) -> Self {
+        let size = bounds.size().max(Vec2::splat(1.0));
+        let fit = (rect.width() / size.x).min(rect.height() / size.y) * 0.9;
+        Self {
+            center: rect.center() + layout.offset,
+            world_center: bounds.center().to_vec2(),
+            scale: fit * layout.zoom,
+        }
+    }
+
+    /// north is up
+    pub(crate) fn to_screen(&self, x: f32, y: f32) -> Pos2 {
+        self.center + Vec2::new(x - self.world_center.x, -(y - self.world_center.y)) * self.scale
+    }
+
+    pub(crate) fn to_world(&self, pos: Pos2) -> (f32, f32) {
+        let d = (pos - self.center) / self.scale;
+        (d.x + self.world_center.x, -d.y + self.world_center.y)
+    }
+
+    /// Converts a screen distance to a world distance
+    pub(crate) fn to_world_delta(&self, delta: Vec2) -> (f32, f32) {
+        (delta.x / self.scale, -delta.y / self.scale)
+    }
+}
+
+/// World bounds of a cell: the exterior cell or the extent of all references
+pub(crate) fn get_cell_bounds(cell: &Cell) -> Rect {
+    if !cell.is_interior() {
+        let (x, y) = cell.data.grid;
+        let min = Pos2::new(x as f32, y as f32) * EXTERIOR_CELL_SIZE;
+        return Rect::from_min_size(min, Vec2::splat(EXTERIOR_CELL_SIZE));
+    }
+
+    let mut bounds = Rect::NOTHING;
+    for reference in cell.references.values() {
+        bounds.extend_with(Pos2::new(
+            reference.translation[0],
+            reference.translation[1],
+        ));
+    }
+    if bounds.is_negative() {
+        Rect::from_center_size(Pos2::ZERO, Vec2::splat(1024.0))
+    } else {
+        bounds.expand(256.0)
+    }
+}
+
+/// Pans with the secondary or middle mouse button and zooms with the scroll wheel
+pub(crate) fn handle_pan_zoom(ui: &egui::Ui, response: &Response, layout: &mut LayoutData) {
+    if response.dragged_by(egui::PointerButton::Secondary)
+        || response.dragged_by(egui::PointerButton::Middle)
+    {
+        layout.offset += response.drag_delta();
+    }
+    if response.hovered() {
+        let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+        if scroll != 0.0 {
+            let old_zoom = layout.zoom;
+            let new_zoom = (old_zoom * (1.0 + scroll * 0.002)).clamp(0.05, 100.0);
+            // zoom around the pointer
+            if let Some(pointer) = response.hover_pos() {
+                let from_center = pointer - (response.rect.center() + layout.offset);
+                layout.offset += from_center - from_center * (new_zoom / old_zoom);
+            }
+            layout.zoom = new_zoom;
+        }
+    }
+}
+
+/// Color of a reference by the type of its base object
+pub(crate) fn get_type_color(tag: Option<&String>) -> Color32 {
+    match tag.map(|t| t.as_str()) {
+        Some("STAT") => Color32::GRAY,
+        Some("NPC_") => Color32::GREEN,
+        Some("CREA") => Color32::from_rgb(255, 140, 0),
+        Some("DOOR") => Color32::from_rgb(160, 100, 50),
+        Some("CONT") => Color32::YELLOW,
+        Some("LIGH") => Color32::from_rgb(255, 255, 180),
+        Some("ACTI") => Color32::from_rgb(180, 80, 220),
+        Some("LEVC") | Some("LEVI") => Color32::from_rgb(255, 100, 180),
+        Some(_) => Color32::LIGHT_BLUE,
+        None => Color32::WHITE,
+    }
+}
+
+/// Paints all references of a cell as points
+pub(crate) fn paint_references(
+    painter: &egui::Painter,
+    transform: &LayoutTransform,
+    cell: &Cell,
+    layout: &LayoutData,
+    alpha: f32,
+) {
+    for (key, reference) in cell.references.iter() {
+        let pos = transform.to_screen(reference.translation[0], reference.translation[1]);
+        if !painter.clip_rect().contains(pos) {
+            continue;
+        }
+        let color = get_type_color(layout.base_types.get(&reference.id.to_lowercase()));
+        painter.circle_filled(pos, 3.0, color.gamma_multiply(alpha));
+        if layout.selected_reference == Some(*key) {
+            painter.circle_stroke(pos, 6.0, Stroke::new(2.0, Color32::WHITE));
+        }
+    }
+}
+
+/// Returns the reference nearest to a screen position within the pick radius
+fn pick_reference(transform: &LayoutTransform, cell: &Cell, pos: Pos2) -> Option<(u32, u32)> {
+    cell.references
+        .iter()
+        .map(|(key, r)| {
+            let distance = transform
+                .to_screen(r.translation[0], r.translation[1])
+                .distance(pos);
+            (*key, distance)
+        })
+        .filter(|(_, distance)| *distance < PICK_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(key, _)| key)
+}
+
+/// Collects the record types of all base objects of the open plugins by lowercase id
+pub(crate) fn get_base_types(plugins: &[PluginMetadata]) -> HashMap<String, String> {
+    let mut base_types = HashMap::default();
+    for plugin in plugins {
+        for record in plugin
+            .records
+            .values()
+            .chain(plugin.edited_records.values())
+        {
+            if matches!(
+                record,
+                TES3Object::Header(_)
+                    | TES3Object::Cell(_)
+                    | TES3Object::Landscape(_)
+                    | TES3Object::PathGrid(_)
+                    | TES3Object::Dialogue(_)
+                    | TES3Object::DialogueInfo(_)
+            ) {
+                continue;
+            }
+            base_types.insert(
+                record.editor_id().to_lowercase(),
+                record.tag_str().to_string(),
+            );
+        }
+    }
+    base_types
+}
+
+/// List of the references of a cell with the position of the selected reference
+/// The selection is shared by the layout and the fields of the cell
+pub(crate) fn reference_list_view(ui: &mut egui::Ui, cell: &mut Cell, layout: &mut LayoutData) {
+    ui.label(format!("References: {}", cell.references.len()));
+    ui.separator();
+
+    // position of the selected reference
+    if let Some(reference) = layout
+        .selected_reference
+        .and_then(|key| cell.references.get_mut(&key))
+    {
+        ui.label(reference.id.clone());
+        ui.horizontal(|ui| {
+            for value in reference.translation.iter_mut() {
+                ui.add(egui::DragValue::new(value).speed(1.0));
+            }
+        });
+        ui.separator();
+    }
+
+    let mut keys = cell.references.keys().copied().collect::<Vec<_>>();
+    keys.sort();
+    egui::ScrollArea::vertical()
+        .id_salt("cell_references")
+        .show(ui, |ui| {
+            for key in keys {
+                let reference = &cell.references[&key];
+                let selected = layout.selected_reference == Some(key);
+                let color = get_type_color(layout.base_types.get(&reference.id.to_lowercase()));
+                let response = ui
+                    .horizontal(|ui| {
+                        ui.colored_label(color, "●");
+                        ui.selectable_label(
+                            selected,
+                            format!("{} ({}, {})", reference.id, key.0, key.1),
+                        )
+                    })
+                    .inner;
+                if response.clicked() {
+                    layout.selected_reference = Some(key);
+                }
+                if selected && layout.scroll_to_selected {
+                    response.scroll_to_me(Some(egui::Align::Center));
+                }
+            }
+        });
+    layout.scroll_to_selected = false;
+}
+
+/// Top-down layout of all references of a cell
+/// References are selected with a click and moved by dragging
+pub(crate) fn cell_layout_view(ui: &mut egui::Ui, cell: &mut Cell, layout: &mut LayoutData) {
+    // reference list
+    let mut scroll_to_selected = false;
+    egui::SidePanel::left("cell_layout_references")
+        .min_width(200_f32)
+        .show_inside(ui, |ui| reference_list_view(ui, cell, layout));
+
+    // plot
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        handle_pan_zoom(ui, &response, layout);
+
+        // the bounds of interiors follow the references, keep them until the drag ends
+        let bounds = match layout.drag_bounds {
+            Some(bounds) if response.dragged() => bounds,
+            _ => get_cell_bounds(cell),
+        };
+        layout.drag_bounds = response.dragged().then_some(bounds);
+        let transform = LayoutTransform::new(response.rect, bounds, layout);
+        painter.rect_filled(response.rect, 0.0, ui.visuals().extreme_bg_color);
+
+        // cell border of exteriors
+        if !cell.is_interior() {
+            let rect = Rect::from_two_pos(
+                transform.to_screen(bounds.min.x, bounds.min.y),
+                transform.to_screen(bounds.max.x, bounds.max.y),
+            );
+            painter.rect_stroke(
+                rect,
+                0.0,
+                Stroke::new(1.0, Color32::DARK_GRAY),
+                egui::StrokeKind::Inside,
+            );
+        }
+
+        paint_references(&painter, &transform, cell, layout, 1.0);
+
+        // select and drag references
+        if let Some(pos) = response.interact_pointer_pos() {
+            if response.drag_started_by(egui::PointerButton::Primary) || response.clicked() {
+                layout.selected_reference = pick_reference(&transform, cell, pos);
+                scroll_to_selected = true;
+            }
+        }
+        if response.dragged_by(egui::PointerButton::Primary) {
+            if let Some(reference) = layout
+                .selected_reference
+                .and_then(|key| cell.references.get_mut(&key))
+            {
+                let (dx, dy) = transform.to_world_delta(response.drag_delta());
+                reference.translation[0] += dx;
+                reference.translation[1] += dy;
+            }
+        }
+
+        // hover
+        if let Some(pos) = response.hover_pos() {
+            if let Some(key) = pick_reference(&transform, cell, pos) {
+                let reference = &cell.references[&key];
+                response.clone().on_hover_text_at_pointer(format!(
+                    "{}\n{:.0}, {:.0}, {:.0}",
+                    reference.id,
+                    reference.translation[0],
+                    reference.translation[1],
+                    reference.translation[2]
+                ));
+            }
+        }
+    });
+    layout.scroll_to_selected = scroll_to_selected;
+}
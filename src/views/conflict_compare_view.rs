@@ -1,6 +1,5 @@
-use crate::{get_unique_id, CompareData, TemplateApp, UiData};
+use crate::{get_record_fields, get_unique_id, CompareData, TemplateApp, UiData};
 use egui::epaint::ahash::HashMap;
-use tes3::esp::editor::Editor;
 
 impl TemplateApp {
     pub fn conflict_compare_view(&mut self, ui: &mut egui::Ui) {
@@ -73,7 +72,7 @@ impl TemplateApp {
 ///
 /// Panics if .
 fn get_ui_data(compare_data: &CompareData, key: String) -> UiData {
-    if let Some(conflicts) = compare_data.map.get(&key) {
+    if let Some(conflicts) = compare_data.conflicts.get(&key) {
         let mut vms: Vec<(String, tes3::esp::Plugin)> = vec![];
         for mod_hash in conflicts {
             let vm = compare_data
@@ -82,26 +81,21 @@ fn get_ui_data(compare_data: &CompareData, key: String) -> UiData {
                 .find(|e| e.id == *mod_hash)
                 .unwrap();
             // mod name
-            let mod_name = vm.path.file_name().unwrap().to_string_lossy().to_string();
+            let mod_name = vm.get_name();
             vms.push((mod_name, vm.plugin.clone()));
         }
 
         // get column map
         let mut columns: Vec<(String, Vec<(String, String)>)> = vec![];
-        for (id, plugin) in vms.iter_mut() {
+        for (id, plugin) in vms.iter() {
             let record = plugin
                 .objects
-                .iter_mut()
+                .iter()
                 .find(|e| get_unique_id(e) == key)
                 .unwrap();
 
             // get fields of record
-            let mut fields: Vec<(String, String)> = vec![];
-            if let Some(record_fields) = record.get_editor_list() {
-                for (field_name, field) in record_fields {
-                    get_fields_recursive(field, &mut fields, field_name.to_owned());
-                }
-            }
+            let fields = get_record_fields(record);
 
             columns.push((id.to_string(), fields));
         }
@@ -153,21 +147,3 @@ fn get_ui_data(compare_data: &CompareData, key: String) -> UiData {
         }
     }
 }
-
-/// .
-fn get_fields_recursive(
-    field: &mut dyn Editor,
-    fields: &mut Vec<(String, String)>,
-    field_name: String,
-) {
-    if let Some(sub) = field.get_editor_list() {
-        // if that field is atype that has itself fields
-        //we need to recursively get them
-        for (field_name2, field2) in sub {
-            let complex_name = format!("{}.{}", field_name, field_name2);
-            get_fields_recursive(field2, fields, complex_name);
-        }
-    } else {
-        fields.push((field_name, field.to_json()));
-    }
-}
@@ -1,6 +1,55 @@
 use crate::TemplateApp;
 
 impl TemplateApp {
+    /// Plugins of the compare, toggling or moving a plugin only updates the conflicts of that plugin
+    fn compare_plugins_view(&mut self, ui: &mut egui::Ui) {
+        let mut toggled = None;
+        let mut moved = None;
+        let mut reloaded = None;
+        egui::CollapsingHeader::new("Plugins").show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("compare_plugins")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    let count = self.compare_data.plugins.len();
+                    for (i, vm) in self.compare_data.plugins.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let mut enabled = vm.enabled;
+                            if ui.checkbox(&mut enabled, vm.get_name()).changed() {
+                                toggled = Some((i, enabled));
+                            }
+                            if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                                moved = Some((i, true));
+                            }
+                            if ui
+                                .add_enabled(i + 1 < count, egui::Button::new("⏷"))
+                                .clicked()
+                            {
+                                moved = Some((i, false));
+                            }
+                            if ui
+                                .add_enabled(vm.enabled, egui::Button::new("⟳"))
+                                .on_hover_text("Reload")
+                                .clicked()
+                            {
+                                reloaded = Some(i);
+                            }
+                        });
+                    }
+                });
+        });
+
+        if let Some((index, enabled)) = toggled {
+            self.set_compare_plugin_enabled(index, enabled);
+        }
+        if let Some((index, up)) = moved {
+            self.move_compare_plugin(index, up);
+        }
+        if let Some(index) = reloaded {
+            self.reload_compare_plugin(index);
+        }
+    }
+
     pub fn conflict_list_view(&mut self, ui: &mut egui::Ui) {
         // heading
         ui.heading("Conflicts");
@@ -13,9 +62,13 @@ impl TemplateApp {
         });
         ui.separator();
 
+        self.compare_plugins_view(ui);
+        ui.separator();
+
         // list of conflicting records
+        ui.label(format!("{} conflicts", self.compare_data.conflicts.len()));
         egui::ScrollArea::vertical().show(ui, |ui| {
-            for key in self.compare_data.conflicting_ids.iter() {
+            for key in self.compare_data.conflicts.get_conflicting_ids() {
                 // TODO upper and lowercase search
                 if !self.edit_data.search_text.is_empty()
                     && !key
@@ -0,0 +1,244 @@
+use egui::{Color32, Stroke};
+use tes3::esp::{DialogueType, TES3Object};
+
+use crate::{
+    dialogue::{get_speaker_conditions, get_topic_infos, insert_info, move_info},
+    filters::format_filter,
+    EAppState, TemplateApp,
+};
+
+impl TemplateApp {
+    pub fn dialogue_menu_bar_view(&mut self, ui: &mut egui::Ui) {
+        // Menu Bar
+        egui::menu::bar(ui, |ui| {
+            if ui.button("Exit").clicked() {
+                self.app_state = EAppState::SingleEdit;
+            }
+
+            ui.separator();
+
+            if let Some(data) = self
+                .edit_data
+                .plugins
+                .iter()
+                .find(|p| p.id == self.edit_data.current_plugin_id)
+            {
+                ui.label(data.id.clone());
+            }
+        });
+    }
+
+    /// All topics of the current plugin grouped by type
+    pub fn dialogue_topics_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Topics");
+
+        let Some(data) = self
+            .edit_data
+            .plugins
+            .iter()
+            .find(|p| p.id == self.edit_data.current_plugin_id)
+        else {
+            ui.label("Please open a plugin first");
+            return;
+        };
+
+        // search bar
+        ui.horizontal(|ui| {
+            ui.label("Filter: ");
+            ui.text_edit_singleline(&mut self.dialogue_data.search_text);
+        });
+        ui.separator();
+
+        let filter = self.dialogue_data.search_text.to_lowercase();
+        let mut topics = data
+            .records
+            .iter()
+            .chain(data.edited_records.iter())
+            .filter_map(|(id, record)| match record {
+                TES3Object::Dialogue(dialogue) => {
+                    Some((id.clone(), dialogue.id.clone(), dialogue.dialogue_type))
+                }
+                _ => None,
+            })
+            .filter(|(_, name, _)| name.to_lowercase().contains(&filter))
+            .collect::<Vec<_>>();
+        topics.sort_by(|a, b| a.0.cmp(&b.0));
+        topics.dedup_by(|a, b| a.0 == b.0);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for dialogue_type in [
+                DialogueType::Topic,
+                DialogueType::Greeting,
+                DialogueType::Persuasion,
+                DialogueType::Voice,
+                DialogueType::Journal,
+            ] {
+                let topics_by_type = topics
+                    .iter()
+                    .filter(|(_, _, t)| *t == dialogue_type)
+                    .collect::<Vec<_>>();
+                if topics_by_type.is_empty() {
+                    continue;
+                }
+
+                egui::CollapsingHeader::new(format!("{:?}", dialogue_type)).show(ui, |ui| {
+                    for (id, name, _) in topics_by_type {
+                        let selected = self.dialogue_data.selected_topic.as_ref() == Some(id);
+                        if ui.selectable_label(selected, name).clicked() {
+                            self.dialogue_data.selected_topic = Some(id.clone());
+                            self.dialogue_data.selected_info = None;
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    /// The INFOs of the selected topic in chain order
+    /// INFOs are reordered by dragging their handle
+    pub fn dialogue_infos_view(&mut self, ui: &mut egui::Ui) {
+        let Some(topic_id) = self.dialogue_data.selected_topic.clone() else {
+            ui.label("Select a topic");
+            return;
+        };
+        let Some(data) = self
+            .edit_data
+            .plugins
+            .iter_mut()
+            .find(|p| p.id == self.edit_data.current_plugin_id)
+        else {
+            return;
+        };
+
+        let info_ids = get_topic_infos(data, &topic_id);
+
+        // heading
+        ui.horizontal(|ui| {
+            ui.heading(topic_id.get(5..).unwrap_or_default());
+            ui.label(format!("{} INFOs", info_ids.len()));
+            if ui.button("Add INFO").clicked() {
+                if let Some(id) = insert_info(data, &topic_id, info_ids.len()) {
+                    self.dialogue_data.selected_info = Some(id);
+                } else {
+                    self.toasts.warning("Could not create INFO");
+                }
+            }
+        });
+        ui.separator();
+
+        let mut moved = None;
+        let mut inserted = None;
+        let mut open = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (index, info_id) in info_ids.iter().enumerate() {
+                let record = data
+                    .edited_records
+                    .get(info_id)
+                    .or_else(|| data.records.get(info_id));
+                let Some(TES3Object::DialogueInfo(info)) = record else {
+                    continue;
+                };
+
+                let selected = self.dialogue_data.selected_info.as_ref() == Some(info_id);
+                let edited = data.edited_records.contains_key(info_id);
+                let row = egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.horizontal(|ui| {
+                        // drag handle
+                        ui.dnd_drag_source(egui::Id::new(("info_drag", info_id)), index, |ui| {
+                            ui.label("☰");
+                        });
+                        ui.label(format!("{}.", index + 1));
+
+                        let mut text = info.text.lines().next().unwrap_or_default().to_owned();
+                        if edited {
+                            text = format!("{}*", text);
+                        }
+                        let response = ui.selectable_label(selected, text);
+                        if response.clicked() {
+                            self.dialogue_data.selected_info = Some(info_id.clone());
+                        }
+                        if response.double_clicked() {
+                            open = Some(info_id.clone());
+                        }
+
+                        // insert new INFOs
+                        response.context_menu(|ui| {
+                            if ui.button("Insert above").clicked() {
+                                inserted = Some(index);
+                                ui.close_menu();
+                            }
+                            if ui.button("Insert below").clicked() {
+                                inserted = Some(index + 1);
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui.button("Open in editor").clicked() {
+                                open = Some(info_id.clone());
+                                ui.close_menu();
+                            }
+                        });
+                    });
+
+                    // speaker conditions and filters
+                    let conditions = get_speaker_conditions(info);
+                    if !conditions.is_empty() {
+                        ui.label(conditions.join(", "));
+                    }
+                    for filter in info.filters.iter() {
+                        ui.label(format_filter(filter));
+                    }
+                    if selected {
+                        ui.label(info.text.clone());
+                        ui.label(format!("ID: {}", info.id));
+                    }
+                });
+
+                // drop zone
+                let response = row.response;
+                if let (Some(pointer), Some(_)) = (
+                    ui.input(|i| i.pointer.interact_pos()),
+                    response.dnd_hover_payload::<usize>(),
+                ) {
+                    let y = if pointer.y < response.rect.center().y {
+                        response.rect.top()
+                    } else {
+                        response.rect.bottom()
+                    };
+                    ui.painter().hline(
+                        response.rect.x_range(),
+                        y,
+                        Stroke::new(2.0, Color32::WHITE),
+                    );
+                }
+                if let Some(from) = response.dnd_release_payload::<usize>() {
+                    let pointer_y = ui
+                        .input(|i| i.pointer.interact_pos())
+                        .map(|p| p.y)
+                        .unwrap_or_default();
+                    let to = if pointer_y < response.rect.center().y {
+                        index
+                    } else {
+                        index + 1
+                    };
+                    moved = Some((*from, to));
+                }
+            }
+        });
+
+        if let Some((from, to)) = moved {
+            move_info(data, &topic_id, from, to);
+        }
+        if let Some(position) = inserted {
+            if let Some(id) = insert_info(data, &topic_id, position) {
+                self.dialogue_data.selected_info = Some(id);
+            } else {
+                self.toasts.warning("Could not create INFO");
+            }
+        }
+        if let Some(info_id) = open {
+            let plugin_id = self.edit_data.current_plugin_id.clone();
+            self.open_record_in_editor(&plugin_id, &info_id);
+        }
+    }
+}
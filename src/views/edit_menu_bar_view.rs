@@ -1,7 +1,7 @@
 use std::path::Path;
 
 #[cfg(not(target_arch = "wasm32"))]
-use crate::get_plugin_id;
+use crate::{clean::clean_plugin, get_plugin_id, sqlite::export_sqlite};
 
 use crate::{
     get_plugin_names, get_unique_id, save_patch, save_plugin, EAppState, EModalState, EScale,
@@ -128,6 +128,77 @@ impl TemplateApp {
 
                 ui.separator();
 
+                // Clean plugin button
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Clean Plugin").clicked() {
+                    if let Some(data) = self
+                        .edit_data
+                        .plugins
+                        .iter()
+                        .find(|p| p.id == self.edit_data.current_plugin_id)
+                    {
+                        if let Some(path) = &data.full_path {
+                            clean_plugin(data, path, &mut self.toasts);
+                        } else {
+                            // log error
+                            self.toasts.error("Please use Save As first");
+                        }
+                    }
+
+                    ui.close_menu();
+                }
+
+                // Sqlite export of the current plugin
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Export SQLite").clicked() {
+                    if let Some(data) = self
+                        .edit_data
+                        .plugins
+                        .iter()
+                        .find(|p| p.id == self.edit_data.current_plugin_id)
+                    {
+                        let name = data
+                            .full_path
+                            .as_ref()
+                            .and_then(|p| p.file_name())
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_else(|| data.id.clone());
+                        let some_path = rfd::FileDialog::new()
+                            .add_filter("sqlite", &["sqlite", "db"])
+                            .set_directory(&self.last_directory)
+                            .set_file_name(format!("{}.sqlite", name))
+                            .save_file();
+                        if let Some(path) = some_path {
+                            // edited records replace their original
+                            let records = data
+                                .records
+                                .iter()
+                                .filter(|(id, _)| !data.edited_records.contains_key(*id))
+                                .chain(data.edited_records.iter())
+                                .map(|(_, record)| record)
+                                .collect::<Vec<_>>();
+                            match export_sqlite(&path, &[(name, records)]) {
+                                Ok(_) => {
+                                    self.toasts.success("Plugin exported");
+                                }
+                                Err(error) => {
+                                    self.toasts.error(format!("Could not export: {}", error));
+                                }
+                            }
+                        }
+                    }
+
+                    ui.close_menu();
+                }
+
+                // Heightmap export and import
+                if ui.button("Heightmap").clicked() {
+                    self.open_modal_window(ui, EModalState::Heightmap);
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
                 // Quit button
                 #[cfg(not(target_arch = "wasm32"))]
                 if ui.button("Quit").clicked() {
@@ -138,22 +209,35 @@ impl TemplateApp {
             // View Menu
             ui.menu_button("View", |ui| {
                 if self.use_experimental && ui.button("Compare View").clicked() {
-                    if !self.edit_data.plugins.is_empty() {
-                        self.toasts
-                            .warning("Please close all open plugins before entering compare mode");
-                    } else {
-                        self.open_modal_window(ui, EModalState::ModalCompareInit);
-                    }
+                    self.open_modal_window(ui, EModalState::ModalCompareInit);
                     ui.close_menu();
                 }
 
                 if ui.button("Records View").clicked() {
-                    if !self.edit_data.plugins.is_empty() {
-                        self.toasts
-                            .warning("Please close all open plugins before entering compare mode");
-                    } else {
-                        self.app_state = EAppState::Records;
-                    }
+                    self.app_state = EAppState::Records;
+                    ui.close_menu();
+                }
+
+                if ui.button("Map View").clicked() {
+                    self.map_data.cells.clear();
+                    self.app_state = EAppState::Map;
+                    ui.close_menu();
+                }
+
+                if ui.button("Dialogue View").clicked() {
+                    self.app_state = EAppState::Dialogue;
+                    ui.close_menu();
+                }
+
+                if ui.button("Quest View").clicked() {
+                    self.quest_data.quests.clear();
+                    self.app_state = EAppState::Quests;
+                    ui.close_menu();
+                }
+
+                if ui.button("Table View").clicked() {
+                    self.table_data.source = None;
+                    self.app_state = EAppState::Table;
                     ui.close_menu();
                 }
 
@@ -252,12 +336,7 @@ impl TemplateApp {
             ui.separator();
 
             if self.use_experimental && ui.button("↔ Compare").clicked() {
-                if !self.edit_data.plugins.is_empty() {
-                    self.toasts
-                        .warning("Please close all open plugins before entering compare mode");
-                } else {
-                    self.open_modal_window(ui, EModalState::ModalCompareInit);
-                }
+                self.open_modal_window(ui, EModalState::ModalCompareInit);
             }
 
             // theme button on right
@@ -1,9 +1,56 @@
-use tes3::esp::editor::Editor;
+use tes3::esp::{editor::Editor, TES3Object};
 
-use crate::TemplateApp;
+use crate::{
+    gmst::{format_gmst_value, is_evil_gmst, is_same_value},
+    pathgrid::find_cell,
+    script::get_globals,
+    views::{
+        cell_layout_view::{cell_layout_view, get_base_types, reference_list_view},
+        filters_view::filters_view,
+        pathgrid_view::pathgrid_view,
+        script_view::{compiled_data_view, script_view},
+    },
+    EEditorTab, LayoutData, ScriptEditorData, TemplateApp,
+};
 
 impl TemplateApp {
     pub fn record_editor_view(&mut self, ui: &mut egui::Ui) {
+        // reset the layout when the selected record changes
+        if let Some(record_id) = self
+            .edit_data
+            .plugins
+            .iter()
+            .find(|p| p.id == self.edit_data.current_plugin_id)
+            .and_then(|p| p.selected_record_id.clone())
+        {
+            if record_id != self.edit_data.layout.record_id {
+                self.edit_data.layout = LayoutData {
+                    record_id,
+                    ..Default::default()
+                };
+                self.edit_data.script = ScriptEditorData::default();
+            }
+        }
+        // the fields of a cell list its references too
+        if (self.edit_data.editor_tab != EEditorTab::Fields
+            || self.edit_data.layout.record_id.starts_with("CELL,"))
+            && self.edit_data.layout.base_types.is_empty()
+        {
+            self.edit_data.layout.base_types = get_base_types(&self.edit_data.plugins);
+            self.edit_data.script.globals = get_globals(&self.edit_data.plugins);
+
+            // the cell of a pathgrid is drawn below it
+            let pathgrid = self
+                .edit_data
+                .plugins
+                .iter()
+                .find(|p| p.id == self.edit_data.current_plugin_id)
+                .and_then(|p| p.edited_records.get(&self.edit_data.layout.record_id));
+            if let Some(TES3Object::PathGrid(pathgrid)) = pathgrid {
+                self.edit_data.layout.pathgrid_cell = find_cell(pathgrid, &self.edit_data.plugins);
+            }
+        }
+
         // editor for a specific plugin
         if let Some(plugin_data) = self
             .edit_data
@@ -25,9 +72,141 @@ impl TemplateApp {
                             self.toasts.info("Record reverted");
                         }
                     }
+
+                    ui.separator();
+
+                    // editor tabs depending on the record type
+                    let tag = current_record_id.split(',').next().unwrap_or_default();
+                    ui.selectable_value(
+                        &mut self.edit_data.editor_tab,
+                        EEditorTab::Fields,
+                        "Fields",
+                    );
+                    if tag == "CELL" {
+                        ui.selectable_value(
+                            &mut self.edit_data.editor_tab,
+                            EEditorTab::Layout,
+                            "Layout",
+                        );
+                    }
+                    if tag == "INFO" {
+                        ui.selectable_value(
+                            &mut self.edit_data.editor_tab,
+                            EEditorTab::Filters,
+                            "Filters",
+                        );
+                    }
+                    if tag == "SCPT" {
+                        ui.selectable_value(
+                            &mut self.edit_data.editor_tab,
+                            EEditorTab::Script,
+                            "Script",
+                        );
+                        ui.selectable_value(
+                            &mut self.edit_data.editor_tab,
+                            EEditorTab::CompiledData,
+                            "Compiled data",
+                        );
+                    }
+                    if tag == "PGRD" {
+                        ui.selectable_value(
+                            &mut self.edit_data.editor_tab,
+                            EEditorTab::Pathgrid,
+                            "Pathgrid",
+                        );
+                    }
                 });
                 ui.separator();
 
+                // top-down layout of cell references
+                if self.edit_data.editor_tab == EEditorTab::Layout {
+                    if let Some(TES3Object::Cell(cell)) =
+                        plugin_data.edited_records.get_mut(current_record_id)
+                    {
+                        cell_layout_view(ui, cell, &mut self.edit_data.layout);
+                        return;
+                    }
+                }
+
+                // pathgrid graph editor
+                if self.edit_data.editor_tab == EEditorTab::Pathgrid {
+                    if let Some(TES3Object::PathGrid(pathgrid)) =
+                        plugin_data.edited_records.get_mut(current_record_id)
+                    {
+                        pathgrid_view(ui, pathgrid, &mut self.edit_data.layout, &mut self.toasts);
+                        return;
+                    }
+                }
+
+                // dialogue filter conditions
+                if self.edit_data.editor_tab == EEditorTab::Filters {
+                    if let Some(TES3Object::DialogueInfo(info)) =
+                        plugin_data.edited_records.get_mut(current_record_id)
+                    {
+                        filters_view(ui, info);
+                        return;
+                    }
+                }
+
+                // script text editor
+                if self.edit_data.editor_tab == EEditorTab::Script {
+                    if let Some(TES3Object::Script(script)) =
+                        plugin_data.edited_records.get_mut(current_record_id)
+                    {
+                        script_view(ui, script, &mut self.edit_data.script);
+                        return;
+                    }
+                }
+
+                // read-only text from the compiled data
+                if self.edit_data.editor_tab == EEditorTab::CompiledData {
+                    if let Some(TES3Object::Script(script)) =
+                        plugin_data.edited_records.get(current_record_id)
+                    {
+                        compiled_data_view(ui, script, &mut self.edit_data.script);
+                        return;
+                    }
+                }
+
+                // vanilla GMST defaults
+                let record = plugin_data
+                    .edited_records
+                    .get(current_record_id)
+                    .or_else(|| plugin_data.records.get(current_record_id));
+                if let Some(TES3Object::GameSetting(gmst)) = record {
+                    if let Some(default) = self.edit_data.gmst_defaults.get(&gmst.id) {
+                        egui::Grid::new("gmst_default_grid").show(ui, |ui| {
+                            ui.label("Vanilla default");
+                            ui.label(format_gmst_value(&default));
+                            ui.end_row();
+                            ui.label("Plugin value");
+                            ui.label(format_gmst_value(&gmst.value));
+                            ui.end_row();
+                        });
+
+                        if is_same_value(&gmst.value, &default) {
+                            let text = if is_evil_gmst(gmst) {
+                                "Evil GMST: this resets a value changed by Tribunal or Bloodmoon"
+                            } else {
+                                "This GMST resets the vanilla value for all plugins loaded before"
+                            };
+                            ui.colored_label(egui::Color32::RED, text);
+                        }
+                        ui.separator();
+                    }
+                }
+
+                // references of a cell, selected together with the layout
+                if let Some(TES3Object::Cell(cell)) =
+                    plugin_data.edited_records.get_mut(current_record_id)
+                {
+                    egui::SidePanel::left("cell_fields_references")
+                        .min_width(200_f32)
+                        .show_inside(ui, |ui| {
+                            reference_list_view(ui, cell, &mut self.edit_data.layout)
+                        });
+                }
+
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     // get the record to edit from the original records or the edited ones
                     if plugin_data.edited_records.contains_key(current_record_id) {
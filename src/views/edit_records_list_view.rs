@@ -1,7 +1,9 @@
 use strum::IntoEnumIterator;
 use tes3::esp::TypeInfo;
 
-use crate::{create, create_from_tag, get_all_tags, get_unique_id, ERecordType, TemplateApp};
+use crate::{
+    create, create_from_tag, get_all_tags, get_unique_id, EQuerySource, ERecordType, TemplateApp,
+};
 
 impl TemplateApp {
     pub fn records_list_view(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
@@ -38,6 +40,21 @@ impl TemplateApp {
         ui.horizontal(|ui| {
             ui.label("Filter: ");
             ui.text_edit_singleline(&mut self.edit_data.search_text);
+            if ui
+                .button("🔍")
+                .on_hover_text("Search script and dialogue text")
+                .clicked()
+            {
+                self.text_search_data.open = true;
+            }
+            if ui
+                .button("⚙")
+                .on_hover_text("Query records by field values")
+                .clicked()
+            {
+                self.query_data.source = EQuerySource::CurrentPlugin;
+                self.query_data.open = true;
+            }
         });
         ui.separator();
 
@@ -73,6 +90,9 @@ impl TemplateApp {
 
         let mut clicked = false;
 
+        // evil GMSTs are annotated in the list
+        let evil_gmsts = data.cached_evil_gmsts.clone();
+
         // the record list
         let mut record_ids_to_delete = vec![];
         egui::ScrollArea::vertical().show(ui, |ui| {
@@ -92,6 +112,9 @@ impl TemplateApp {
                         if id.starts_with("TES3,") {
                             label = "Header".into();
                         }
+                        if evil_gmsts.contains(id) {
+                            label = format!("{} (evil)", label);
+                        }
                         // modified records
                         if data.edited_records.contains_key(id) {
                             label = format!("{}*", label);
@@ -161,33 +184,7 @@ impl TemplateApp {
 
                         // selected event
                         if response.clicked() {
-                            // cleanup old records
-                            let mut to_remove = Vec::new();
-                            for (key, edited_record) in data.edited_records.clone() {
-                                if let Some(original) = data.records.get(&key) {
-                                    // remove if no change
-                                    if original.eq(&edited_record) {
-                                        to_remove.push(key);
-                                    }
-                                }
-                            }
-                            for r in to_remove {
-                                data.edited_records.remove(&r);
-                            }
-
-                            // add a copy of this record to the edited records
-                            if !data.edited_records.contains_key(id) {
-                                // get record
-                                let mut record_or_none = data.records.get(id);
-                                if data.edited_records.contains_key(id) {
-                                    record_or_none = data.edited_records.get(id);
-                                }
-                                if let Some(record) = record_or_none {
-                                    data.edited_records.insert(id.clone(), record.clone());
-                                }
-                            }
-
-                            data.selected_record_id = Some(id.to_string());
+                            data.select_record(id);
 
                             clicked = true;
                         }
@@ -197,8 +194,12 @@ impl TemplateApp {
                 // context menu of tag header
                 if tag != "TES3" {
                     tag_header.header_response.context_menu(|ui| {
-                        // add record button
-                        if ui.button("Add record").clicked() {
+                        // add record button, INFOs are added to a topic in the dialogue view
+                        if ui
+                            .add_enabled(tag != "INFO", egui::Button::new("Add record"))
+                            .on_disabled_hover_text("Add INFOs to a topic in the Dialogue view")
+                            .clicked()
+                        {
                             if let Some(instance) = create_from_tag(&tag.clone()) {
                                 let new_id = get_unique_id(&instance);
                                 data.edited_records.insert(new_id.clone(), instance);
@@ -210,6 +211,19 @@ impl TemplateApp {
                             ui.close_menu();
                         }
 
+                        // strip evil GMSTs button
+                        if tag == "GMST"
+                            && !evil_gmsts.is_empty()
+                            && ui.button("Strip evil GMSTs").clicked()
+                        {
+                            for id in evil_gmsts.iter() {
+                                record_ids_to_delete.push(id.clone());
+                            }
+                            self.toasts
+                                .success(format!("Removed {} evil GMSTs", evil_gmsts.len()));
+                            ui.close_menu();
+                        }
+
                         ui.separator();
 
                         // delete all button
@@ -258,6 +272,7 @@ impl TemplateApp {
                     }
                     // update HashMap
                     if let Some(v) = data.edited_records.remove(&old_key) {
+                        data.rename_record(&old_key, &new_key);
                         data.edited_records.insert(new_key, v);
                         data.records.remove(&old_key);
                     }
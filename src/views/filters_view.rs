@@ -0,0 +1,118 @@
+use strum::IntoEnumIterator;
+use tes3::esp::{DialogueInfo, Filter, FilterValue};
+
+use crate::filters::{
+    decode_filter, encode_filter, format_filter, get_free_filter_index, DecodedFilter, EComparison,
+    EFilterType, EVariableType, FUNCTIONS,
+};
+
+/// Editor for the filter conditions of an INFO
+/// Filters are decoded into rows and encoded back when a row changes
+pub(crate) fn filters_view(ui: &mut egui::Ui, info: &mut DialogueInfo) {
+    let mut to_remove = None;
+    egui::Grid::new("info_filters_grid")
+        .striped(true)
+        .show(ui, |ui| {
+            for (i, filter) in info.filters.iter_mut().enumerate() {
+                ui.label(filter.index.to_string());
+
+                let Some(mut decoded) = decode_filter(filter) else {
+                    // unknown encodings are only shown
+                    ui.label(format_filter(filter));
+                    if ui.button("Remove").clicked() {
+                        to_remove = Some(i);
+                    }
+                    ui.end_row();
+                    continue;
+                };
+
+                let old = decoded.clone();
+                filter_row(ui, i, &mut decoded);
+                if decoded != old {
+                    encode_filter(&decoded, filter);
+                }
+
+                if ui.button("Remove").clicked() {
+                    to_remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+    if let Some(i) = to_remove {
+        info.filters.remove(i);
+    }
+
+    // filters are stored in index order, a removed filter frees its index
+    let free_index = get_free_filter_index(&info.filters);
+    ui.add_enabled_ui(free_index.is_some(), |ui| {
+        if ui.button("Add filter").clicked() {
+            let Some(index) = free_index else {
+                return;
+            };
+            let mut filter = Filter {
+                index,
+                ..Default::default()
+            };
+            encode_filter(&DecodedFilter::default(), &mut filter);
+            let position = info.filters.partition_point(|f| f.index < index);
+            info.filters.insert(position, filter);
+        }
+    });
+}
+
+/// Dropdowns for one decoded filter
+fn filter_row(ui: &mut egui::Ui, i: usize, decoded: &mut DecodedFilter) {
+    egui::ComboBox::from_id_salt(("filter_type", i))
+        .selected_text(decoded.filter_type.to_string())
+        .show_ui(ui, |ui| {
+            for filter_type in EFilterType::iter() {
+                ui.selectable_value(
+                    &mut decoded.filter_type,
+                    filter_type,
+                    filter_type.to_string(),
+                );
+            }
+        });
+
+    if decoded.filter_type == EFilterType::Function {
+        egui::ComboBox::from_id_salt(("filter_function", i))
+            .selected_text(FUNCTIONS[decoded.function])
+            .show_ui(ui, |ui| {
+                for (index, name) in FUNCTIONS.iter().enumerate() {
+                    ui.selectable_value(&mut decoded.function, index, *name);
+                }
+            });
+    } else {
+        ui.horizontal(|ui| {
+            if decoded.filter_type.is_variable() {
+                egui::ComboBox::from_id_salt(("filter_variable", i))
+                    .selected_text(decoded.variable_type.to_string())
+                    .show_ui(ui, |ui| {
+                        for variable_type in EVariableType::iter() {
+                            ui.selectable_value(
+                                &mut decoded.variable_type,
+                                variable_type,
+                                variable_type.to_string(),
+                            );
+                        }
+                    });
+            }
+            ui.text_edit_singleline(&mut decoded.id);
+        });
+    }
+
+    egui::ComboBox::from_id_salt(("filter_comparison", i))
+        .selected_text(decoded.comparison.to_string())
+        .width(40.0)
+        .show_ui(ui, |ui| {
+            for comparison in EComparison::iter() {
+                ui.selectable_value(&mut decoded.comparison, comparison, comparison.to_string());
+            }
+        });
+
+    match &mut decoded.value {
+        FilterValue::Float(v) => ui.add(egui::DragValue::new(v).speed(0.1)),
+        FilterValue::Integer(v) => ui.add(egui::DragValue::new(v)),
+    };
+}
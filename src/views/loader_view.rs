@@ -0,0 +1,45 @@
+use crate::loader::{ELoadStatus, PluginLoader};
+
+/// Progress and per-file status of a plugin loader, returns true if cancel was clicked
+pub(crate) fn loader_view(ui: &mut egui::Ui, loader: &PluginLoader) -> bool {
+    let total = loader.files.len();
+    let finished = loader.get_finished_count();
+    let mut cancel = false;
+
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::ProgressBar::new(finished as f32 / total.max(1) as f32)
+                .desired_width(300.0)
+                .text(format!("{} / {} plugins", finished, total)),
+        );
+        cancel = ui.button("Cancel").clicked();
+    });
+
+    egui::CollapsingHeader::new("Files")
+        .id_salt("loader_files")
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("loader_files_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (i, (_, status)) in loader.files.iter().enumerate() {
+                                ui.label(loader.get_name(i));
+                                match status {
+                                    ELoadStatus::Failed(_) => {
+                                        ui.colored_label(egui::Color32::RED, status.to_string())
+                                    }
+                                    ELoadStatus::Done => {
+                                        ui.colored_label(egui::Color32::GREEN, status.to_string())
+                                    }
+                                    _ => ui.label(status.to_string()),
+                                };
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+    ui.separator();
+    cancel
+}
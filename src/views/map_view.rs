@@ -0,0 +1,290 @@
+use std::{collections::HashMap, path::Path};
+
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Vec2};
+use strum::IntoEnumIterator;
+use tes3::esp::TES3Object;
+
+use crate::{EAppState, EMapMode, MapCell, PluginMetadata, TemplateApp};
+
+/// Size of a cell on the map in points at zoom 1
+const CELL_SIZE: f32 = 12.0;
+
+impl TemplateApp {
+    pub fn map_menu_bar_view(&mut self, ui: &mut egui::Ui) {
+        // Menu Bar
+        egui::menu::bar(ui, |ui| {
+            if ui.button("Exit").clicked() {
+                self.app_state = EAppState::SingleEdit;
+            }
+
+            ui.separator();
+
+            // map mode
+            egui::ComboBox::from_label("Mode")
+                .selected_text(self.map_data.mode.to_string())
+                .show_ui(ui, |ui| {
+                    for mode in EMapMode::iter() {
+                        ui.selectable_value(&mut self.map_data.mode, mode, mode.to_string());
+                    }
+                });
+
+            if ui.button("Refresh").clicked() {
+                self.map_data.cells.clear();
+            }
+
+            if ui.button("Reset view").clicked() {
+                self.map_data.zoom = 1.0;
+                self.map_data.offset = Vec2::ZERO;
+            }
+        });
+    }
+
+    /// Details of the selected cell
+    pub fn map_side_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Cell");
+        ui.separator();
+
+        let Some(grid) = self.map_data.selected else {
+            ui.label("Click a cell on the map");
+            return;
+        };
+        let Some(cell) = self.map_data.cells.get(&grid) else {
+            ui.label(format!("Wilderness ({}, {})", grid.0, grid.1));
+            return;
+        };
+
+        egui::Grid::new("map_cell_grid").show(ui, |ui| {
+            ui.label("Grid");
+            ui.label(format!("{}, {}", grid.0, grid.1));
+            ui.end_row();
+            ui.label("Name");
+            ui.label(&cell.name);
+            ui.end_row();
+            ui.label("Region");
+            ui.label(cell.region.clone().unwrap_or_default());
+            ui.end_row();
+        });
+        ui.separator();
+
+        // open the cell record in one of its plugins
+        ui.label("Plugins");
+        let mut open = None;
+        for plugin_id in cell.plugins.iter() {
+            ui.horizontal(|ui| {
+                ui.colored_label(color_from_name(plugin_id), "■");
+                ui.label(get_plugin_name(plugin_id));
+                if ui.button("Open").clicked() {
+                    open = Some((plugin_id.clone(), cell.id.clone()));
+                }
+            });
+        }
+
+        if let Some((plugin_id, record_id)) = open {
+            self.open_record_in_editor(&plugin_id, &record_id);
+        }
+    }
+
+    /// Exterior grid map of all open plugins
+    pub fn map_view(&mut self, ui: &mut egui::Ui) {
+        if self.map_data.cells.is_empty() {
+            self.map_data.cells = get_map_cells(&self.edit_data.plugins);
+        }
+
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        let rect = response.rect;
+
+        // pan and zoom
+        if response.dragged() {
+            self.map_data.offset += response.drag_delta();
+        }
+        if response.hovered() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                let old_zoom = self.map_data.zoom;
+                let new_zoom = (old_zoom * (1.0 + scroll * 0.002)).clamp(0.1, 20.0);
+                // zoom around the pointer
+                if let Some(pointer) = response.hover_pos() {
+                    let center = rect.center() + self.map_data.offset;
+                    let from_center = pointer - center;
+                    self.map_data.offset += from_center - from_center * (new_zoom / old_zoom);
+                }
+                self.map_data.zoom = new_zoom;
+            }
+        }
+
+        let size = CELL_SIZE * self.map_data.zoom;
+        let center = rect.center() + self.map_data.offset;
+        let to_screen = |grid: (i32, i32)| -> Rect {
+            // north is up
+            let min = center + Vec2::new(grid.0 as f32 * size, -(grid.1 as f32 + 1.0) * size);
+            Rect::from_min_size(min, Vec2::splat(size))
+        };
+        let to_grid = |pos: Pos2| -> (i32, i32) {
+            let x = ((pos.x - center.x) / size).floor() as i32;
+            let y = (-(pos.y - center.y) / size).floor() as i32;
+            (x, y)
+        };
+
+        // background
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        // cells
+        for (grid, cell) in self.map_data.cells.iter() {
+            let cell_rect = to_screen(*grid);
+            if !rect.intersects(cell_rect) {
+                continue;
+            }
+            let color = match self.map_data.mode {
+                EMapMode::Regions => cell
+                    .region
+                    .as_ref()
+                    .map(|r| color_from_name(&r.to_lowercase()))
+                    .unwrap_or(Color32::GRAY),
+                EMapMode::Plugins => cell
+                    .plugins
+                    .last()
+                    .map(|p| color_from_name(p))
+                    .unwrap_or(Color32::GRAY),
+                EMapMode::Conflicts => conflict_color(cell.plugins.len().saturating_sub(1)),
+            };
+            painter.rect_filled(cell_rect.shrink(size * 0.05), 0.0, color);
+        }
+
+        // origin cross
+        painter.line_segment(
+            [
+                Pos2::new(center.x, rect.top()),
+                Pos2::new(center.x, rect.bottom()),
+            ],
+            Stroke::new(1.0, Color32::from_white_alpha(16)),
+        );
+        painter.line_segment(
+            [
+                Pos2::new(rect.left(), center.y),
+                Pos2::new(rect.right(), center.y),
+            ],
+            Stroke::new(1.0, Color32::from_white_alpha(16)),
+        );
+
+        // selection
+        if let Some(grid) = self.map_data.selected {
+            painter.rect_stroke(
+                to_screen(grid),
+                0.0,
+                Stroke::new(2.0, Color32::WHITE),
+                egui::StrokeKind::Outside,
+            );
+        }
+
+        // hover
+        if let Some(pos) = response.hover_pos() {
+            let grid = to_grid(pos);
+            painter.rect_stroke(
+                to_screen(grid),
+                0.0,
+                Stroke::new(1.0, Color32::LIGHT_GRAY),
+                egui::StrokeKind::Outside,
+            );
+
+            let text = match self.map_data.cells.get(&grid) {
+                Some(cell) => {
+                    let mut text = format!("{} ({}, {})", cell.name, grid.0, grid.1);
+                    if let Some(region) = &cell.region {
+                        text = format!("{}\n{}", text, region);
+                    }
+                    for plugin_id in cell.plugins.iter() {
+                        text = format!("{}\n{}", text, get_plugin_name(plugin_id));
+                    }
+                    text
+                }
+                None => format!("({}, {})", grid.0, grid.1),
+            };
+            response.clone().on_hover_text_at_pointer(text);
+        }
+
+        // select a cell, double click opens the cell in the last plugin touching it
+        if let Some(pos) = response.interact_pointer_pos() {
+            if response.clicked() {
+                self.map_data.selected = Some(to_grid(pos));
+            }
+            if response.double_clicked() {
+                if let Some(cell) = self.map_data.cells.get(&to_grid(pos)) {
+                    if let Some(plugin_id) = cell.plugins.last() {
+                        let (plugin_id, record_id) = (plugin_id.clone(), cell.id.clone());
+                        self.open_record_in_editor(&plugin_id, &record_id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Switches to the editor and selects the record in the given plugin
+    pub(crate) fn open_record_in_editor(&mut self, plugin_id: &str, record_id: &str) {
+        if let Some(data) = self
+            .edit_data
+            .plugins
+            .iter_mut()
+            .find(|p| p.id == plugin_id)
+        {
+            data.select_record(record_id);
+            self.edit_data.current_plugin_id = plugin_id.to_owned();
+            self.app_state = EAppState::SingleEdit;
+        }
+    }
+}
+
+/// Collects all exterior cells of the given plugins by grid
+fn get_map_cells(plugins: &[PluginMetadata]) -> HashMap<(i32, i32), MapCell> {
+    let mut cells: HashMap<(i32, i32), MapCell> = HashMap::default();
+    for plugin in plugins {
+        let records = plugin.records.iter().chain(plugin.edited_records.iter());
+        for (id, record) in records {
+            let TES3Object::Cell(cell) = record else {
+                continue;
+            };
+            if cell.is_interior() {
+                continue;
+            }
+
+            let map_cell = cells.entry(cell.data.grid).or_insert_with(|| MapCell {
+                id: id.clone(),
+                ..Default::default()
+            });
+            if !cell.name.is_empty() {
+                map_cell.name.clone_from(&cell.name);
+            }
+            if cell.region.is_some() {
+                map_cell.region.clone_from(&cell.region);
+            }
+            if !map_cell.plugins.contains(&plugin.id) {
+                map_cell.plugins.push(plugin.id.clone());
+            }
+        }
+    }
+    cells
+}
+
+/// A stable color for a name
+fn color_from_name(name: &str) -> Color32 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    std::hash::Hash::hash(name, &mut hasher);
+    let hash = std::hash::Hasher::finish(&hasher);
+    let hue = (hash % 360) as f32 / 360.0;
+    egui::ecolor::Hsva::new(hue, 0.6, 0.8, 1.0).into()
+}
+
+/// Green for no conflicts to red for many
+fn conflict_color(conflicts: usize) -> Color32 {
+    if conflicts == 0 {
+        return Color32::DARK_GREEN;
+    }
+    let t = (conflicts as f32 / 4.0).min(1.0);
+    Color32::from_rgb(200, (200.0 * (1.0 - t)) as u8, 0)
+}
+
+fn get_plugin_name(plugin_id: &str) -> String {
+    Path::new(plugin_id)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| plugin_id.to_owned())
+}
@@ -3,10 +3,111 @@ use std::{env, fs, path::PathBuf};
 use tes3::esp::Plugin;
 
 use crate::{
-    generate_conflict_map, get_path_hash, get_unique_id, CompareData, EAppState, TemplateApp,
+    get_path_hash, get_unique_id, loader::PluginLoader, CompareData, EAppState, PluginMetadata,
+    TemplateApp,
 };
 
 impl TemplateApp {
+    /// Parses a plugin of the compare in the background
+    fn load_compare_plugin(&mut self, index: usize) {
+        let path = self.compare_data.plugins[index].path.clone();
+        match &mut self.compare_data.loader {
+            Some(loader) => loader.add(path),
+            None => self.compare_data.loader = Some(PluginLoader::new(vec![path])),
+        }
+    }
+
+    /// Takes the plugins the loader finished, the conflicts of the first load are built at once
+    pub(crate) fn poll_compare_loader(&mut self) {
+        let Some(loader) = &mut self.compare_data.loader else {
+            return;
+        };
+        let loaded = loader
+            .poll()
+            .into_iter()
+            .map(|(index, loaded_plugin)| (loader.files[index].0.clone(), loaded_plugin))
+            .collect::<Vec<_>>();
+        let finished = loader.is_finished();
+
+        for (path, loaded_plugin) in loaded {
+            let Some(index) = self
+                .compare_data
+                .plugins
+                .iter()
+                .position(|e| !e.in_memory && e.path == path)
+            else {
+                continue;
+            };
+            let vm = &mut self.compare_data.plugins[index];
+            vm.records = loaded_plugin.records;
+            vm.plugin = loaded_plugin.plugin;
+            if vm.enabled && self.compare_data.conflicts_built {
+                self.compare_data.index_plugin(index);
+            }
+        }
+
+        if finished {
+            self.compare_data.loader = None;
+            if !self.compare_data.conflicts_built {
+                self.compare_data.build_conflicts();
+            }
+            self.toasts.success("Loaded plugins");
+        }
+    }
+
+    /// Enables or disables a plugin of the compare, plugins that were not loaded yet are loaded
+    pub(crate) fn set_compare_plugin_enabled(&mut self, index: usize, enabled: bool) {
+        let vm = &mut self.compare_data.plugins[index];
+        vm.enabled = enabled;
+        if !enabled {
+            self.compare_data.unindex_plugin(index);
+        } else if vm.in_memory || !vm.records.is_empty() {
+            self.compare_data.index_plugin(index);
+        } else {
+            self.load_compare_plugin(index);
+        }
+    }
+
+    /// Reads a plugin of the compare again, open plugins are taken from edit mode
+    pub(crate) fn reload_compare_plugin(&mut self, index: usize) {
+        let vm = &mut self.compare_data.plugins[index];
+        if !vm.in_memory {
+            self.load_compare_plugin(index);
+            return;
+        }
+
+        let Some(open_plugin) = self
+            .edit_data
+            .plugins
+            .iter()
+            .find(|p| p.full_path.clone().unwrap_or_else(|| PathBuf::from(&p.id)) == vm.path)
+        else {
+            self.toasts.warning("The plugin is not open anymore");
+            return;
+        };
+        vm.plugin = Plugin {
+            objects: open_plugin.get_records_sorted(),
+        };
+        vm.records = vm.plugin.objects.iter().map(get_unique_id).collect();
+        if vm.enabled {
+            self.compare_data.index_plugin(index);
+        }
+    }
+
+    /// Moves a plugin of the compare one place up or down in the load order
+    pub(crate) fn move_compare_plugin(&mut self, index: usize, up: bool) {
+        let other = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1)
+        };
+        let Some(other) = other.filter(|o| *o < self.compare_data.plugins.len()) else {
+            return;
+        };
+        self.compare_data.plugins.swap(index, other);
+        self.compare_data.update_conflict_order();
+    }
+
     /// Returns the update modal compare of this [`TemplateApp`].
     pub(crate) fn update_modal_compare(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
@@ -16,33 +117,20 @@ impl TemplateApp {
                     // go into compare mode
                     self.app_state = EAppState::Compare;
 
-                    // calculate conflicts
-                    // load plugins into memory
-                    for vm in self.compare_data.plugins.iter_mut().filter(|e| e.enabled) {
-                        if let Ok(plugin) = Plugin::from_path(vm.path.clone()) {
-                            vm.plugin = plugin;
-                            vm.records = vm
-                                .plugin
-                                .objects
-                                .iter()
-                                .map(get_unique_id)
-                                .collect::<Vec<_>>();
-                        }
-                    }
-
-                    let conflict_map = generate_conflict_map(&self.compare_data);
-                    self.compare_data.map = conflict_map;
-                    let mut keys = self
+                    // load plugins into memory in the background
+                    let paths = self
                         .compare_data
-                        .map
-                        .keys()
-                        .map(|e| e.to_owned())
+                        .plugins
+                        .iter()
+                        .filter(|e| e.enabled && !e.in_memory)
+                        .map(|e| e.path.clone())
                         .collect::<Vec<_>>();
-                    keys.sort();
-                    self.compare_data.conflicting_ids = keys;
+                    self.compare_data.loader = Some(PluginLoader::new(paths));
+                    // all plugins are indexed at once when the loader finished
+                    self.compare_data.conflicts = Default::default();
+                    self.compare_data.conflicts_built = false;
 
                     // close modal window
-                    self.toasts.success("Loaded plugins");
                     self.close_modal_window(ui);
                 }
 
@@ -62,7 +150,7 @@ impl TemplateApp {
                 }
             }
             if self.compare_data.plugins.is_empty() {
-                populate_plugins(&mut self.compare_data);
+                populate_plugins(&mut self.compare_data, &self.edit_data.plugins);
             }
 
             // Main view
@@ -73,7 +161,7 @@ impl TemplateApp {
             ui.horizontal(|ui| {
                 ui.label(self.compare_data.path.display().to_string());
                 if ui.button("🗁").clicked() {
-                    open_compare_folder(&mut self.compare_data);
+                    open_compare_folder(&mut self.compare_data, &self.edit_data.plugins);
                 }
             });
             ui.separator();
@@ -101,7 +189,7 @@ impl TemplateApp {
                     for vm in self.compare_data.plugins.iter_mut() {
                         ui.horizontal(|ui| {
                             ui.checkbox(&mut vm.enabled, "");
-                            ui.label(vm.path.file_name().unwrap().to_string_lossy());
+                            ui.label(vm.get_name());
                         });
                     }
                 });
@@ -111,7 +199,7 @@ impl TemplateApp {
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn open_compare_folder(data: &mut CompareData) {
+fn open_compare_folder(data: &mut CompareData, open_plugins: &[PluginMetadata]) {
     let folder_option = rfd::FileDialog::new().pick_folder();
     if let Some(path) = folder_option {
         if !path.is_dir() {
@@ -119,11 +207,11 @@ fn open_compare_folder(data: &mut CompareData) {
         }
 
         data.path = path;
-        populate_plugins(data);
+        populate_plugins(data, open_plugins);
     }
 }
 
-fn populate_plugins(data: &mut CompareData) {
+fn populate_plugins(data: &mut CompareData, open_plugins: &[PluginMetadata]) {
     data.plugins.clear();
 
     // get plugins
@@ -135,6 +223,7 @@ fn populate_plugins(data: &mut CompareData) {
             enabled: false,
             plugin: Plugin { objects: vec![] },
             records: vec![],
+            in_memory: false,
         })
         .collect::<Vec<_>>();
 
@@ -155,4 +244,23 @@ fn populate_plugins(data: &mut CompareData) {
                     .unwrap(),
             )
     });
+
+    // open plugins with their unsaved changes load last
+    for open_plugin in open_plugins {
+        let path = open_plugin
+            .full_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&open_plugin.id));
+        let plugin = Plugin {
+            objects: open_plugin.get_records_sorted(),
+        };
+        data.plugins.push(crate::CompareItemViewModel {
+            id: get_path_hash(&PathBuf::from(format!("{}:open", open_plugin.id))),
+            path,
+            enabled: false,
+            records: plugin.objects.iter().map(get_unique_id).collect(),
+            plugin,
+            in_memory: true,
+        });
+    }
 }
@@ -0,0 +1,120 @@
+use strum::IntoEnumIterator;
+
+use crate::{heightmap::EHeightmapFormat, EModalState, TemplateApp};
+
+impl TemplateApp {
+    #[allow(unused_variables)] // for wasm
+    pub fn update_heightmap(&mut self, ctx: &egui::Context) {
+        let mut open = self.modal_open;
+        egui::Window::new("Heightmap")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.heading("Landscape heights");
+                ui.separator();
+
+                let Some(data) = self
+                    .edit_data
+                    .plugins
+                    .iter_mut()
+                    .find(|p| p.id == self.edit_data.current_plugin_id)
+                else {
+                    ui.label("Please open a plugin first");
+                    return;
+                };
+
+                // cell range
+                ui.checkbox(
+                    &mut self.heightmap_data.whole_worldspace,
+                    "Whole worldspace",
+                );
+                ui.add_enabled_ui(!self.heightmap_data.whole_worldspace, |ui| {
+                    egui::Grid::new("heightmap_range_grid").show(ui, |ui| {
+                        ui.label("South-west cell");
+                        ui.add(egui::DragValue::new(&mut self.heightmap_data.min.0));
+                        ui.add(egui::DragValue::new(&mut self.heightmap_data.min.1));
+                        ui.end_row();
+                        ui.label("North-east cell");
+                        ui.add(egui::DragValue::new(&mut self.heightmap_data.max.0));
+                        ui.add(egui::DragValue::new(&mut self.heightmap_data.max.1));
+                        ui.end_row();
+                    });
+                });
+
+                // format
+                egui::ComboBox::from_label("Format")
+                    .selected_text(self.heightmap_data.format.to_string())
+                    .show_ui(ui, |ui| {
+                        for format in EHeightmapFormat::iter() {
+                            ui.selectable_value(
+                                &mut self.heightmap_data.format,
+                                format,
+                                format.to_string(),
+                            );
+                        }
+                    });
+
+                ui.separator();
+
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.horizontal(|ui| {
+                    if ui.button("Export").clicked() {
+                        let info = if self.heightmap_data.whole_worldspace {
+                            crate::heightmap::get_landscape_bounds(data)
+                        } else {
+                            let (min, max) = (self.heightmap_data.min, self.heightmap_data.max);
+                            Some(crate::heightmap::HeightmapInfo {
+                                min: (min.0.min(max.0), min.1.min(max.1)),
+                                max: (min.0.max(max.0), min.1.max(max.1)),
+                            })
+                        };
+
+                        let format = self.heightmap_data.format;
+                        if let Some(info) = info {
+                            let some_path = rfd::FileDialog::new()
+                                .add_filter(format.extension(), &[format.extension()])
+                                .set_directory(&self.last_directory)
+                                .save_file();
+                            if let Some(path) = some_path {
+                                match crate::heightmap::export_heightmap(data, &info, format, path)
+                                {
+                                    Ok(count) => {
+                                        self.toasts
+                                            .success(format!("Exported {} landscapes", count));
+                                    }
+                                    Err(e) => {
+                                        self.toasts.error(format!("Could not export: {}", e));
+                                    }
+                                }
+                            }
+                        } else {
+                            self.toasts.warning("Plugin has no landscapes");
+                        }
+                    }
+
+                    if ui.button("Import").clicked() {
+                        let some_path = rfd::FileDialog::new()
+                            .add_filter("png", &["png"])
+                            .add_filter("raw", &["raw"])
+                            .set_directory(&self.last_directory)
+                            .pick_file();
+                        if let Some(path) = some_path {
+                            match crate::heightmap::import_heightmap(data, path) {
+                                Ok(count) => {
+                                    self.toasts
+                                        .success(format!("Imported {} landscapes", count));
+                                }
+                                Err(e) => {
+                                    self.toasts.error(format!("Could not import: {}", e));
+                                }
+                            }
+                        }
+                    }
+                });
+            });
+
+        if !open {
+            self.modal_open = false;
+            self.modal_state = EModalState::None;
+        }
+    }
+}
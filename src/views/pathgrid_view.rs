@@ -0,0 +1,227 @@
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Vec2};
+use egui_notify::Toasts;
+use tes3::esp::PathGrid;
+
+use crate::{
+    pathgrid::{
+        add_point, get_adjacency, get_origin, remove_point, toggle_connection, validate_pathgrid,
+    },
+    views::cell_layout_view::{
+        get_cell_bounds, handle_pan_zoom, paint_references, LayoutTransform, PICK_RADIUS,
+    },
+    LayoutData,
+};
+
+/// Returns the point nearest to a screen position within the pick radius
+fn pick_point(
+    transform: &LayoutTransform,
+    pathgrid: &PathGrid,
+    origin: (f32, f32),
+    pos: Pos2,
+) -> Option<usize> {
+    pathgrid
+        .points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let screen = transform.to_screen(
+                origin.0 + p.location[0] as f32,
+                origin.1 + p.location[1] as f32,
+            );
+            (i, screen.distance(pos))
+        })
+        .filter(|(_, distance)| *distance < PICK_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+/// Graph editor of a pathgrid, drawn over the reference layout of its cell
+pub(crate) fn pathgrid_view(
+    ui: &mut egui::Ui,
+    pathgrid: &mut PathGrid,
+    layout: &mut LayoutData,
+    toasts: &mut Toasts,
+) {
+    let origin = get_origin(pathgrid, layout.pathgrid_cell.as_ref());
+    if layout
+        .selected_point
+        .is_some_and(|p| p >= pathgrid.points.len())
+    {
+        layout.selected_point = None;
+    }
+
+    // point details and validation
+    egui::SidePanel::left("pathgrid_points")
+        .min_width(200_f32)
+        .show_inside(ui, |ui| {
+            ui.label(format!(
+                "Points: {}, connections: {}",
+                pathgrid.points.len(),
+                pathgrid.connections.len()
+            ));
+            if layout.pathgrid_cell.is_none() {
+                ui.colored_label(Color32::YELLOW, "Cell not found in the open plugins");
+            }
+            ui.label("Click: select, drag: move");
+            ui.label("Shift+click: add a point connected to the selection");
+            ui.label("Ctrl+click: connect or disconnect with the selection");
+            ui.separator();
+
+            if let Some(index) = layout.selected_point {
+                ui.label(format!("Point {}", index));
+                ui.horizontal(|ui| {
+                    for value in pathgrid.points[index].location.iter_mut() {
+                        ui.add(egui::DragValue::new(value));
+                    }
+                });
+                let connected = get_adjacency(pathgrid)[index]
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                ui.label(format!("Connected to: {}", connected));
+                if ui.button("Delete point").clicked() {
+                    remove_point(pathgrid, index);
+                    layout.selected_point = None;
+                }
+                ui.separator();
+            }
+
+            ui.label("Validation");
+            let issues = validate_pathgrid(pathgrid, layout.pathgrid_cell.as_ref());
+            if issues.is_empty() {
+                ui.colored_label(Color32::GREEN, "No issues found");
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for issue in issues {
+                    let response = ui.add(
+                        egui::Label::new(
+                            egui::RichText::new(issue.to_string()).color(Color32::RED),
+                        )
+                        .sense(Sense::click()),
+                    );
+                    if response.clicked() {
+                        layout.selected_point = issue.point();
+                    }
+                }
+            });
+        });
+
+    // graph
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        handle_pan_zoom(ui, &response, layout);
+
+        // without references the bounds follow the points, keep them until the drag ends
+        let bounds = match (&layout.pathgrid_cell, layout.drag_bounds) {
+            (_, Some(bounds)) if response.dragged() => bounds,
+            (Some(cell), _) if !cell.is_interior() || !cell.references.is_empty() => {
+                get_cell_bounds(cell)
+            }
+            _ => {
+                let mut bounds = Rect::NOTHING;
+                for point in pathgrid.points.iter() {
+                    bounds.extend_with(Pos2::new(
+                        origin.0 + point.location[0] as f32,
+                        origin.1 + point.location[1] as f32,
+                    ));
+                }
+                if bounds.is_negative() {
+                    Rect::from_center_size(Pos2::ZERO, Vec2::splat(1024.0))
+                } else {
+                    bounds.expand(256.0)
+                }
+            }
+        };
+        layout.drag_bounds = response.dragged().then_some(bounds);
+        let transform = LayoutTransform::new(response.rect, bounds, layout);
+        painter.rect_filled(response.rect, 0.0, ui.visuals().extreme_bg_color);
+
+        // references of the cell in the background
+        if let Some(cell) = &layout.pathgrid_cell {
+            paint_references(&painter, &transform, cell, layout, 0.3);
+        }
+
+        let to_screen = |location: [i32; 3]| {
+            transform.to_screen(origin.0 + location[0] as f32, origin.1 + location[1] as f32)
+        };
+
+        // connections
+        for (a, connected) in get_adjacency(pathgrid).iter().enumerate() {
+            for b in connected.iter().filter(|b| **b > a) {
+                painter.line_segment(
+                    [
+                        to_screen(pathgrid.points[a].location),
+                        to_screen(pathgrid.points[*b].location),
+                    ],
+                    Stroke::new(1.5, Color32::LIGHT_GREEN),
+                );
+            }
+        }
+
+        // points
+        for (i, point) in pathgrid.points.iter().enumerate() {
+            let pos = to_screen(point.location);
+            let color = if point.connection_count == 0 {
+                Color32::RED
+            } else {
+                Color32::GREEN
+            };
+            painter.circle_filled(pos, 4.0, color);
+            if layout.selected_point == Some(i) {
+                painter.circle_stroke(pos, 7.0, Stroke::new(2.0, Color32::WHITE));
+            }
+        }
+
+        // edit points
+        let modifiers = ui.input(|i| i.modifiers);
+        if let Some(pos) = response.interact_pointer_pos() {
+            let picked = pick_point(&transform, pathgrid, origin, pos);
+            if response.clicked() {
+                match (picked, layout.selected_point) {
+                    (Some(point), Some(selected)) if modifiers.command => {
+                        if let Err(error) = toggle_connection(pathgrid, selected, point) {
+                            toasts.warning(error);
+                        }
+                    }
+                    (None, selected) if modifiers.shift => {
+                        let (x, y) = transform.to_world(pos);
+                        let z = selected
+                            .map(|s| pathgrid.points[s].location[2])
+                            .unwrap_or_default();
+                        let location = [(x - origin.0) as i32, (y - origin.1) as i32, z];
+                        layout.selected_point = Some(add_point(pathgrid, location, selected));
+                    }
+                    _ => layout.selected_point = picked,
+                }
+            } else if response.drag_started_by(egui::PointerButton::Primary) {
+                layout.selected_point = picked;
+            }
+        }
+        if response.dragged_by(egui::PointerButton::Primary) {
+            // points are integers, so follow the pointer instead of adding up deltas
+            if let (Some(index), Some(pos)) =
+                (layout.selected_point, response.interact_pointer_pos())
+            {
+                let (x, y) = transform.to_world(pos);
+                pathgrid.points[index].location[0] = (x - origin.0).round() as i32;
+                pathgrid.points[index].location[1] = (y - origin.1).round() as i32;
+            }
+        }
+        if response.hovered() && ui.input(|i| i.key_pressed(egui::Key::Delete)) {
+            if let Some(index) = layout.selected_point.take() {
+                remove_point(pathgrid, index);
+            }
+        }
+
+        // hover
+        if let Some(pos) = response.hover_pos() {
+            if let Some(index) = pick_point(&transform, pathgrid, origin, pos) {
+                let [x, y, z] = pathgrid.points[index].location;
+                response
+                    .clone()
+                    .on_hover_text_at_pointer(format!("Point {}\n{}, {}, {}", index, x, y, z));
+            }
+        }
+    });
+}
@@ -0,0 +1,185 @@
+use serde::Serialize;
+use tes3::esp::TES3Object;
+
+use crate::{
+    query::{parse_query, run_query},
+    EQuerySource, TemplateApp,
+};
+
+/// maximum number of results that are listed
+const MAX_RESULTS: usize = 1000;
+
+/// A query result as written to the export file
+#[derive(Serialize)]
+struct QueryExport<'a> {
+    plugin: &'a str,
+    record: &'a TES3Object,
+}
+
+impl TemplateApp {
+    fn run_record_query(&mut self) {
+        let data = &mut self.query_data;
+        let query = match parse_query(&data.text) {
+            Ok(query) => query,
+            Err(error) => {
+                data.results.clear();
+                data.error = Some(error);
+                return;
+            }
+        };
+        data.error = None;
+
+        data.results = match data.source {
+            EQuerySource::CurrentPlugin => {
+                let Some(plugin) = self
+                    .edit_data
+                    .plugins
+                    .iter()
+                    .find(|p| p.id == self.edit_data.current_plugin_id)
+                else {
+                    data.error = Some("Please open a plugin first".into());
+                    return;
+                };
+                // edited records replace their original
+                let records = plugin
+                    .records
+                    .iter()
+                    .filter(|(id, _)| !plugin.edited_records.contains_key(*id))
+                    .chain(plugin.edited_records.iter())
+                    .map(|(_, record)| (plugin.id.as_str(), record));
+                run_query(&query, records)
+            }
+            EQuerySource::LoadOrder => {
+                let records = self.records_data.plugins.iter().flat_map(|(name, plugin)| {
+                    plugin
+                        .objects
+                        .iter()
+                        .map(move |record| (name.as_str(), record))
+                });
+                run_query(&query, records)
+            }
+        };
+        data.results.sort_by(|a, b| a.record_id.cmp(&b.record_id));
+    }
+
+    /// Query box that filters records by field paths
+    pub fn query_window(&mut self, ctx: &egui::Context) {
+        if !self.query_data.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut run = false;
+        let mut jump = None;
+        egui::Window::new("Query records")
+            .open(&mut open)
+            .default_width(700.0)
+            .show(ctx, |ui| {
+                let data = &mut self.query_data;
+                let source = data.source;
+                ui.horizontal(|ui| {
+                    ui.radio_value(
+                        &mut data.source,
+                        EQuerySource::CurrentPlugin,
+                        "Current plugin",
+                    );
+                    ui.radio_value(&mut data.source, EQuerySource::LoadOrder, "Records view");
+                });
+                if source != data.source {
+                    data.results.clear();
+                }
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut data.text)
+                            .hint_text("WEAP where data.damage > 30 and value < 100")
+                            .desired_width(500.0),
+                    );
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        run = true;
+                    }
+                    run |= ui.button("Run").clicked();
+                })
+                .response
+                .on_hover_text(
+                    "Field paths are the dotted names of the Fields editor, \
+                    the last parts are enough\n\
+                    Operators: = != < <= > >= ~ (contains), and, or, not, ( )",
+                );
+                if let Some(error) = &data.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} records", data.results.len()));
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if !data.results.is_empty() && ui.button("Export").clicked() {
+                        let some_path = rfd::FileDialog::new()
+                            .add_filter("yaml", &["yaml"])
+                            .set_file_name("query.yaml")
+                            .save_file();
+                        if let Some(path) = some_path {
+                            let export = data
+                                .results
+                                .iter()
+                                .map(|r| QueryExport {
+                                    plugin: &r.plugin,
+                                    record: &r.record,
+                                })
+                                .collect::<Vec<_>>();
+                            let result = serde_yaml::to_string(&export)
+                                .map_err(|e| e.to_string())
+                                .and_then(|text| {
+                                    std::fs::write(path, text).map_err(|e| e.to_string())
+                                });
+                            match result {
+                                Ok(_) => {
+                                    self.toasts.success("Query results exported");
+                                }
+                                Err(error) => {
+                                    self.toasts.error(format!("Could not export: {}", error));
+                                }
+                            }
+                        }
+                    }
+                });
+
+                egui::ScrollArea::both().show(ui, |ui| {
+                    egui::Grid::new("query_results_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for result in data.results.iter().take(MAX_RESULTS) {
+                                if data.source == EQuerySource::CurrentPlugin {
+                                    if ui.button("Open").clicked() {
+                                        jump =
+                                            Some((result.plugin.clone(), result.record_id.clone()));
+                                    }
+                                } else {
+                                    ui.label(&result.plugin);
+                                }
+                                ui.label(&result.record_id);
+                                let fields = result
+                                    .fields
+                                    .iter()
+                                    .map(|(name, value)| format!("{} = {}", name, value))
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+                                ui.label(fields);
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if run {
+            self.run_record_query();
+        }
+        if !open {
+            self.query_data.open = false;
+        }
+        if let Some((plugin_id, record_id)) = jump {
+            self.open_record_in_editor(&plugin_id, &record_id);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{
+    quests::{find_journal_calls, get_quests},
+    EAppState, TemplateApp,
+};
+
+impl TemplateApp {
+    pub fn quest_menu_bar_view(&mut self, ui: &mut egui::Ui) {
+        // Menu Bar
+        egui::menu::bar(ui, |ui| {
+            if ui.button("Exit").clicked() {
+                self.app_state = EAppState::SingleEdit;
+            }
+
+            ui.separator();
+
+            if ui.button("Refresh").clicked() {
+                self.quest_data.quests.clear();
+                self.quest_data.calls.clear();
+            }
+        });
+    }
+
+    /// All journals of the open plugins
+    pub fn quest_list_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Quests");
+
+        if self.quest_data.quests.is_empty() {
+            self.quest_data.quests = get_quests(&self.edit_data.plugins);
+        }
+
+        // search bar
+        ui.horizontal(|ui| {
+            ui.label("Filter: ");
+            ui.text_edit_singleline(&mut self.quest_data.search_text);
+        });
+        ui.separator();
+
+        let filter = self.quest_data.search_text.to_lowercase();
+        let mut selected = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for quest in self.quest_data.quests.iter() {
+                let name = quest.name.clone().unwrap_or_default();
+                if !quest.id.to_lowercase().contains(&filter)
+                    && !name.to_lowercase().contains(&filter)
+                {
+                    continue;
+                }
+
+                let is_selected = self.quest_data.selected.as_ref() == Some(&quest.id);
+                let label = if name.is_empty() {
+                    quest.id.clone()
+                } else {
+                    format!("{} ({})", quest.id, name)
+                };
+                if ui.selectable_label(is_selected, label).clicked() {
+                    selected = Some(quest.id.clone());
+                }
+            }
+        });
+
+        if let Some(id) = selected {
+            self.quest_data.calls = find_journal_calls(&self.edit_data.plugins, &id);
+            self.quest_data.selected = Some(id);
+        }
+    }
+
+    /// Stages of the selected quest and the records changing its journal
+    pub fn quest_view(&mut self, ui: &mut egui::Ui) {
+        let Some(quest) = self
+            .quest_data
+            .selected
+            .as_ref()
+            .and_then(|id| self.quest_data.quests.iter().find(|q| &q.id == id))
+        else {
+            ui.label("Select a quest");
+            return;
+        };
+
+        ui.heading(quest.name.clone().unwrap_or_else(|| quest.id.clone()));
+        ui.label(format!("ID: {}", quest.id));
+        ui.separator();
+
+        let mut open = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            // stages
+            egui::Grid::new("quest_stages_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Index");
+                    ui.strong("Finished");
+                    ui.strong("Restart");
+                    ui.strong("Text");
+                    ui.end_row();
+
+                    for stage in quest.stages.iter() {
+                        ui.label(stage.index.to_string());
+                        ui.label(if stage.finished { "✔" } else { "" });
+                        ui.label(if stage.restart { "✔" } else { "" });
+                        let response = ui.add(
+                            egui::Label::new(&stage.text)
+                                .wrap()
+                                .sense(egui::Sense::click()),
+                        );
+                        if response.double_clicked() {
+                            open = Some((stage.plugin_id.clone(), stage.info_id.clone()));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+
+            // journal calls
+            ui.strong("Journal changes");
+            if self.quest_data.calls.is_empty() {
+                ui.label("None found in the open plugins");
+            }
+            egui::Grid::new("quest_calls_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for call in self.quest_data.calls.iter() {
+                        ui.label(
+                            std::path::Path::new(&call.plugin_id)
+                                .file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_default(),
+                        );
+                        ui.label(&call.record_id);
+                        ui.monospace(&call.line);
+                        if ui.button("Open").clicked() {
+                            open = Some((call.plugin_id.clone(), call.record_id.clone()));
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if let Some((plugin_id, record_id)) = open {
+            self.open_record_in_editor(&plugin_id, &record_id);
+        }
+    }
+}
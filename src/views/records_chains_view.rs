@@ -0,0 +1,75 @@
+use crate::TemplateApp;
+
+impl TemplateApp {
+    /// Results of the INFO chain check of the plugins in the Records view
+    #[allow(unused_variables)] // for wasm
+    pub fn info_chains_window(&mut self, ctx: &egui::Context) {
+        let Some(issues) = &self.records_data.chain_issues else {
+            return;
+        };
+        if issues.is_empty() {
+            self.records_data.chain_issues = None;
+            return;
+        }
+
+        let mut open = true;
+        egui::Window::new("INFO chains")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.label(format!("{} broken links", issues.len()));
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Write repair patch").clicked() {
+                    let some_path = rfd::FileDialog::new()
+                        .add_filter("esp", &["esp"])
+                        .set_directory(&self.compare_data.path)
+                        .set_file_name("info_chains_patch.esp")
+                        .save_file();
+                    if let Some(path) = some_path {
+                        let mut patch = crate::chains::create_chain_patch(
+                            &self.records_data.plugins,
+                            &self.compare_data.path,
+                            issues,
+                        );
+                        match patch.save_path(path) {
+                            Ok(_) => {
+                                self.toasts.success(format!(
+                                    "Repair patch saved with {} records",
+                                    patch.objects.len() - 1
+                                ));
+                            }
+                            Err(_) => {
+                                self.toasts.error("Could not save plugin");
+                            }
+                        }
+                    }
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("info_chains_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Topic");
+                            ui.strong("INFO");
+                            ui.strong("Plugin");
+                            ui.strong("Issue");
+                            ui.end_row();
+
+                            for issue in issues.iter() {
+                                ui.label(&issue.topic);
+                                ui.label(&issue.info_id);
+                                ui.label(&issue.plugin);
+                                ui.label(issue.kind.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if !open {
+            self.records_data.chain_issues = None;
+        }
+    }
+}
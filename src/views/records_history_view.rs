@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use tes3::esp::{Cell, Plugin, Reference, TES3Object};
+
+use crate::{
+    clean::{get_master_names, get_reference_origin},
+    get_record_fields, get_unique_id, TemplateApp, UiData,
+};
+
+/// What to do with a record of the history panel
+enum EHistoryAction {
+    /// open the plugin in edit mode with the record selected
+    Edit(String),
+    /// copy the record into the current plugin
+    Override(String),
+}
+
+/// Field values of a record in every plugin that has it, in load order
+fn get_record_history(plugins: &[(String, Plugin)], key: &str) -> UiData {
+    let mut history = UiData {
+        id: key.to_owned(),
+        ..Default::default()
+    };
+
+    let mut columns = vec![];
+    for (plugin_name, plugin) in plugins {
+        if let Some(record) = plugin.objects.iter().find(|r| get_unique_id(r) == key) {
+            history.plugins.push(plugin_name.clone());
+            columns.push(get_record_fields(record));
+        }
+    }
+
+    // all field names in the order they first appear
+    for fields in columns.iter() {
+        for (field_name, _) in fields {
+            if !history.rows.iter().any(|(n, _)| n == field_name) {
+                history.rows.push((field_name.clone(), vec![]));
+            }
+        }
+    }
+    for (field_name, values) in history.rows.iter_mut() {
+        for fields in columns.iter() {
+            let value = fields
+                .iter()
+                .find(|(n, _)| n == field_name)
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default();
+            values.push(value);
+        }
+    }
+    history
+}
+
+/// Moves the references of a cell from a plugin of the load order into the masters of the target plugin
+/// Plugins the references come from are appended to the target masters if missing
+/// References of masters missing from the source header are dropped and counted
+fn remap_references(
+    cell: &Cell,
+    plugin_name: &str,
+    plugin_masters: &[String],
+    target_name: &str,
+    target_masters: &mut Vec<String>,
+) -> (HashMap<(u32, u32), Reference>, usize) {
+    let mut references = HashMap::new();
+    let mut dropped = 0;
+    for (key, reference) in cell.references.iter() {
+        let Some((origin, refr_index)) = get_reference_origin(*key, plugin_name, plugin_masters)
+        else {
+            dropped += 1;
+            continue;
+        };
+        let mast_index = if origin == target_name.to_lowercase() {
+            0
+        } else {
+            match target_masters
+                .iter()
+                .position(|m| m.to_lowercase() == origin)
+            {
+                Some(i) => i + 1,
+                None => {
+                    let master = plugin_masters
+                        .iter()
+                        .find(|m| m.to_lowercase() == origin)
+                        .map(|m| m.as_str())
+                        .unwrap_or(plugin_name);
+                    target_masters.push(master.to_owned());
+                    target_masters.len()
+                }
+            }
+        } as u32;
+        let mut reference = reference.clone();
+        reference.mast_index = mast_index;
+        references.insert((mast_index, refr_index), reference);
+    }
+    (references, dropped)
+}
+
+impl TemplateApp {
+    /// Opens a plugin of the Records view in edit mode and selects the record
+    fn edit_record_from_load_order(&mut self, plugin_name: &str, key: &str) {
+        let Some(path) = self.records_data.paths.get(plugin_name).cloned() else {
+            return;
+        };
+        let plugin_id = path.to_string_lossy().to_string();
+
+        // keep the edits of a plugin that is already open
+        if !self.edit_data.plugins.iter().any(|p| p.id == plugin_id) {
+            let Some((_, plugin)) = self
+                .records_data
+                .plugins
+                .iter()
+                .find(|(name, _)| name == plugin_name)
+            else {
+                return;
+            };
+            self.open_plugin(Some(path), plugin.clone());
+        }
+        self.open_record_in_editor(&plugin_id, key);
+    }
+
+    /// Copies the version of a record in a plugin of the Records view into the current plugin
+    /// The plugins the references of a CELL come from are added to the masters of the current plugin
+    fn override_record_from_load_order(&mut self, plugin_name: &str, key: &str) {
+        let Some((record, plugin_masters)) = self
+            .records_data
+            .plugins
+            .iter()
+            .find(|(name, _)| name == plugin_name)
+            .and_then(|(_, plugin)| {
+                let record = plugin.objects.iter().find(|r| get_unique_id(r) == key)?;
+                Some((record, get_master_names(plugin)))
+            })
+        else {
+            return;
+        };
+        let Some(data) = self
+            .edit_data
+            .plugins
+            .iter_mut()
+            .find(|p| p.id == self.edit_data.current_plugin_id)
+        else {
+            self.toasts.warning("Please open a plugin first");
+            return;
+        };
+
+        let mut record = record.clone();
+        if let TES3Object::Cell(cell) = &mut record {
+            let Some(TES3Object::Header(mut header)) = data
+                .edited_records
+                .get("TES3,")
+                .or_else(|| data.records.get("TES3,"))
+                .cloned()
+            else {
+                self.toasts.error("The current plugin has no header");
+                return;
+            };
+            let target_name = data
+                .full_path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or(data.id.clone());
+            let mut target_masters = header
+                .masters
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            let (references, dropped) = remap_references(
+                cell,
+                plugin_name,
+                &plugin_masters,
+                &target_name,
+                &mut target_masters,
+            );
+            cell.references = references;
+            if dropped > 0 {
+                self.toasts.warning(format!(
+                    "{} references of masters missing from {} were not copied",
+                    dropped, plugin_name
+                ));
+            }
+
+            let added = target_masters[header.masters.len()..].to_vec();
+            if !added.is_empty() {
+                for master in added.iter() {
+                    let size = self
+                        .records_data
+                        .paths
+                        .get(master)
+                        .or_else(|| {
+                            // masters are listed without the case of the file
+                            self.records_data
+                                .paths
+                                .iter()
+                                .find(|(name, _)| name.eq_ignore_ascii_case(master))
+                                .map(|(_, path)| path)
+                        })
+                        .and_then(|path| std::fs::metadata(path).ok())
+                        .map(|m| m.len())
+                        .unwrap_or_default();
+                    header.masters.push((master.clone(), size));
+                }
+                data.edited_records
+                    .insert("TES3,".to_owned(), TES3Object::Header(header));
+                self.toasts
+                    .info(format!("Added masters: {}", added.join(", ")));
+            }
+        }
+
+        data.edited_records.insert(key.to_owned(), record);
+        data.clear_cache();
+        self.toasts.success(format!(
+            "Copied {} from {} into the current plugin",
+            key, plugin_name
+        ));
+    }
+
+    /// The selected record of the Records view in every plugin, changes are highlighted
+    pub fn record_history_view(&mut self, ui: &mut egui::Ui) {
+        let Some(key) = self.records_data.selected_id.clone() else {
+            ui.label("Select a record");
+            return;
+        };
+
+        if self.records_data.history.as_ref().map(|h| &h.id) != Some(&key) {
+            self.records_data.history = Some(get_record_history(&self.records_data.plugins, &key));
+        }
+        let Some(history) = &self.records_data.history else {
+            return;
+        };
+        let has_current_plugin = self
+            .edit_data
+            .plugins
+            .iter()
+            .any(|p| p.id == self.edit_data.current_plugin_id);
+
+        ui.heading(&key);
+        ui.separator();
+
+        let mut action = None;
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("record_history_grid")
+                .min_col_width(150_f32)
+                .max_col_width(250_f32)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Field");
+                    for plugin in history.plugins.iter() {
+                        ui.strong(plugin);
+                    }
+                    ui.end_row();
+
+                    ui.label("");
+                    for plugin in history.plugins.iter() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("Edit")
+                                .on_hover_text("Open the plugin with this record")
+                                .clicked()
+                            {
+                                action = Some(EHistoryAction::Edit(plugin.clone()));
+                            }
+                            if ui
+                                .add_enabled(has_current_plugin, egui::Button::new("Override"))
+                                .on_hover_text("Copy this version into the current plugin")
+                                .clicked()
+                            {
+                                action = Some(EHistoryAction::Override(plugin.clone()));
+                            }
+                        });
+                    }
+                    ui.end_row();
+
+                    for (field_name, values) in history.rows.iter() {
+                        ui.label(field_name);
+                        for (i, value) in values.iter().enumerate() {
+                            // changed relative to the previous plugin
+                            if i > 0 && values[i - 1] != *value {
+                                ui.colored_label(egui::Color32::RED, value);
+                            } else {
+                                ui.label(value);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        match action {
+            Some(EHistoryAction::Edit(plugin)) => self.edit_record_from_load_order(&plugin, &key),
+            Some(EHistoryAction::Override(plugin)) => {
+                self.override_record_from_load_order(&plugin, &key)
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{cell, get_references, reference};
+
+    use super::*;
+
+    #[test]
+    fn remap_references_to_target_masters() {
+        let cell = cell(vec![
+            reference(0, 5, "rug"),
+            reference(1, 1, "lamp"),
+            reference(2, 3, "chair"),
+        ]);
+        let plugin_masters = ["Morrowind.esm".to_string(), "Target.esp".to_string()];
+        let mut target_masters = vec!["Tribunal.esm".to_string(), "morrowind.esm".to_string()];
+
+        let (references, dropped) = remap_references(
+            &cell,
+            "Source.esp",
+            &plugin_masters,
+            "target.esp",
+            &mut target_masters,
+        );
+        assert_eq!(dropped, 0);
+        // own references of the target need no master, the source plugin is added
+        assert_eq!(
+            get_references(&references),
+            vec![
+                ((0, 3), "chair".to_string()),
+                ((2, 1), "lamp".to_string()),
+                ((3, 5), "rug".to_string()),
+            ]
+        );
+        assert_eq!(
+            target_masters,
+            vec!["Tribunal.esm", "morrowind.esm", "Source.esp"]
+        );
+    }
+
+    #[test]
+    fn remap_references_of_missing_masters() {
+        let cell = cell(vec![reference(1, 1, "lamp"), reference(3, 2, "rug")]);
+        let mut target_masters = vec![];
+
+        let (references, dropped) = remap_references(
+            &cell,
+            "Source.esp",
+            &["Morrowind.esm".to_string()],
+            "Target.esp",
+            &mut target_masters,
+        );
+        assert_eq!(dropped, 1);
+        assert_eq!(
+            get_references(&references),
+            vec![((1, 1), "lamp".to_string())]
+        );
+        assert_eq!(target_masters, vec!["Morrowind.esm"]);
+    }
+}
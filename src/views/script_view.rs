@@ -0,0 +1,187 @@
+use std::{
+    hash::{Hash, Hasher},
+    ops::Range,
+};
+
+use egui::{text::LayoutJob, Color32, FontId, Stroke, TextFormat};
+use tes3::esp::Script;
+
+use crate::{
+    script::{get_cursor_matches, tokenize, validate_script, ETokenKind, ScriptError},
+    script_data::{format_compiled_data, get_script_mismatches},
+    ScriptEditorData,
+};
+
+fn get_token_color(kind: ETokenKind, ui: &egui::Ui) -> Color32 {
+    match kind {
+        ETokenKind::Keyword => Color32::from_rgb(86, 156, 214),
+        ETokenKind::Function => Color32::from_rgb(220, 170, 80),
+        ETokenKind::Number => Color32::from_rgb(140, 190, 120),
+        ETokenKind::String => Color32::from_rgb(206, 145, 120),
+        ETokenKind::Comment => Color32::GRAY,
+        ETokenKind::Identifier | ETokenKind::Operator | ETokenKind::Bracket => {
+            ui.visuals().text_color()
+        }
+    }
+}
+
+/// Highlights the script tokens, marks the cursor matches and underlines lines with errors
+fn highlight_script(
+    ui: &egui::Ui,
+    text: &str,
+    errors: &[ScriptError],
+    matches: &[Range<usize>],
+) -> LayoutJob {
+    let font_id = FontId::monospace(egui::TextStyle::Monospace.resolve(ui.style()).size);
+    let plain = TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+
+    let mut job = LayoutJob::default();
+    let mut end = 0;
+    for token in tokenize(text) {
+        // whitespace between tokens
+        if token.range.start > end {
+            job.append(&text[end..token.range.start], 0.0, plain.clone());
+        }
+
+        let mut format = TextFormat::simple(font_id.clone(), get_token_color(token.kind, ui));
+        if errors.iter().any(|e| e.line == token.line) {
+            format.underline = Stroke::new(1.0, Color32::RED);
+        }
+        if matches.contains(&token.range) {
+            format.background = ui.visuals().selection.bg_fill.gamma_multiply(0.5);
+        }
+        job.append(&text[token.range.clone()], 0.0, format);
+        end = token.range.end;
+    }
+    if end < text.len() {
+        job.append(&text[end..], 0.0, plain);
+    }
+    job
+}
+
+/// Script text editor with line numbers, highlighting and validation
+pub(crate) fn script_view(ui: &mut egui::Ui, script: &mut Script, data: &mut ScriptEditorData) {
+    let errors = data
+        .errors
+        .get_or_insert_with(|| validate_script(&script.text, &data.globals))
+        .clone();
+
+    // errors
+    egui::TopBottomPanel::bottom("script_errors_panel")
+        .resizable(true)
+        .show_inside(ui, |ui| {
+            if errors.is_empty() {
+                ui.colored_label(Color32::GREEN, "No errors");
+                return;
+            }
+            ui.colored_label(Color32::RED, format!("{} errors", errors.len()));
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for error in errors.iter() {
+                    ui.label(error.to_string());
+                }
+            });
+        });
+
+    // the text may have changed since the cursor was stored
+    let matches = data
+        .cursor
+        .map(|index| {
+            let offset = script
+                .text
+                .char_indices()
+                .nth(index)
+                .map(|(i, _)| i)
+                .unwrap_or(script.text.len());
+            get_cursor_matches(&script.text, offset)
+        })
+        .unwrap_or_default();
+    let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+        let mut job = highlight_script(ui, text, &errors, &matches);
+        job.wrap.max_width = wrap_width;
+        ui.fonts(|f| f.layout_job(job))
+    };
+
+    egui::ScrollArea::both().show(ui, |ui| {
+        ui.horizontal_top(|ui| {
+            // line numbers
+            let line_count = script.text.lines().count().max(1);
+            let numbers = (1..=line_count)
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            ui.vertical(|ui| {
+                ui.add_space(2.0);
+                ui.add(egui::Label::new(egui::RichText::new(numbers).monospace().weak()).extend());
+            });
+
+            let output = egui::TextEdit::multiline(&mut script.text)
+                .code_editor()
+                .desired_width(f32::INFINITY)
+                .layouter(&mut layouter)
+                .show(ui);
+            if output.response.changed() {
+                data.errors = None;
+            }
+            data.cursor = output.cursor_range.map(|range| range.primary.ccursor.index);
+        });
+    });
+}
+
+/// Hash of the text and the compiled data of a script
+fn get_script_hash(script: &Script) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    script.text.hash(&mut hasher);
+    script.variables.hash(&mut hasher);
+    script.bytecode.hash(&mut hasher);
+    script.header.num_shorts.hash(&mut hasher);
+    script.header.num_longs.hash(&mut hasher);
+    script.header.num_floats.hash(&mut hasher);
+    script.header.bytecode_length.hash(&mut hasher);
+    script.header.variables_length.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Read-only inspector of the variable table and bytecode texts of a script,
+/// with the declarations and texts that differ from the stored text
+pub(crate) fn compiled_data_view(ui: &mut egui::Ui, script: &Script, data: &mut ScriptEditorData) {
+    // only redone when the script changed
+    let hash = get_script_hash(script);
+    if data
+        .compiled_data
+        .as_ref()
+        .is_none_or(|(h, _, _)| *h != hash)
+    {
+        data.compiled_data = Some((
+            hash,
+            format_compiled_data(script),
+            get_script_mismatches(script),
+        ));
+    }
+    let Some((_, text, mismatches)) = &data.compiled_data else {
+        return;
+    };
+
+    if mismatches.is_empty() {
+        ui.colored_label(
+            Color32::GREEN,
+            "The declarations and texts match the compiled data",
+        );
+    }
+    for mismatch in mismatches {
+        ui.colored_label(Color32::YELLOW, mismatch.to_string());
+    }
+    ui.separator();
+
+    let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+        let mut job = highlight_script(ui, text, &[], &[]);
+        job.wrap.max_width = wrap_width;
+        ui.fonts(|f| f.layout_job(job))
+    };
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::TextEdit::multiline(&mut text.as_str())
+            .code_editor()
+            .desired_width(f32::INFINITY)
+            .layouter(&mut layouter)
+            .show(ui);
+    });
+}
@@ -0,0 +1,373 @@
+use egui_extras::{Column, TableBuilder};
+use strum::IntoEnumIterator;
+
+use crate::{
+    get_record_fields, set_field_value,
+    table::{
+        apply_csv_changes, format_cell, get_table, get_table_row, get_visible_rows, parse_cell,
+        read_csv, write_csv,
+    },
+    EAppState, ERecordType, TemplateApp,
+};
+
+impl TemplateApp {
+    pub fn table_menu_bar_view(&mut self, ui: &mut egui::Ui) {
+        // Menu Bar
+        egui::menu::bar(ui, |ui| {
+            if ui.button("Exit").clicked() {
+                self.app_state = EAppState::SingleEdit;
+            }
+
+            ui.separator();
+
+            let data = &mut self.table_data;
+            egui::ComboBox::from_id_salt("table_record_type")
+                .selected_text(format!("{:?}", data.record_type))
+                .show_ui(ui, |ui| {
+                    for t in ERecordType::iter() {
+                        ui.selectable_value(&mut data.record_type, t, t.to_string());
+                    }
+                });
+
+            ui.label("Filter: ");
+            if ui.text_edit_singleline(&mut data.filter).changed() {
+                data.visible = None;
+            }
+
+            if ui.button("Refresh").clicked() {
+                data.source = None;
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.menu_button("CSV", |ui| {
+                if ui.button("Export all").clicked() {
+                    self.export_table_csv(false);
+                    ui.close_menu();
+                }
+                if ui.button("Export filtered").clicked() {
+                    self.export_table_csv(true);
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button("Import").clicked() {
+                    self.import_table_csv();
+                    ui.close_menu();
+                }
+            });
+
+            ui.separator();
+            ui.label(format!("{} records", self.table_data.rows.len()));
+        });
+    }
+
+    /// Saves the rows of the table as csv
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_table_csv(&mut self, filtered: bool) {
+        let data = &self.table_data;
+        let rows = if filtered {
+            data.visible.clone().unwrap_or_else(|| {
+                get_visible_rows(
+                    &data.rows,
+                    &data.filter,
+                    data.sort_column,
+                    data.sort_ascending,
+                )
+            })
+        } else {
+            (0..data.rows.len()).collect()
+        };
+        let some_path = rfd::FileDialog::new()
+            .add_filter("csv", &["csv"])
+            .set_file_name(format!("{}.csv", data.record_type))
+            .save_file();
+        let Some(path) = some_path else {
+            return;
+        };
+
+        let result = write_csv(&data.columns, rows.iter().map(|i| &data.rows[*i]))
+            .and_then(|text| std::fs::write(path, text).map_err(|e| e.to_string()));
+        match result {
+            Ok(_) => {
+                self.toasts
+                    .success(format!("Exported {} records", rows.len()));
+            }
+            Err(error) => {
+                self.toasts.error(format!("Could not export: {}", error));
+            }
+        }
+    }
+
+    /// Reads a csv and shows its changes for confirmation
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_table_csv(&mut self) {
+        let Some(plugin) = self
+            .edit_data
+            .plugins
+            .iter()
+            .find(|p| p.id == self.edit_data.current_plugin_id)
+        else {
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("csv", &["csv"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let result = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| read_csv(plugin, &text));
+        match result {
+            Ok(import) => self.table_data.csv_import = Some(import),
+            Err(error) => {
+                self.toasts.error(format!("Could not import: {}", error));
+            }
+        }
+    }
+
+    /// Diff preview of an imported csv
+    pub fn csv_import_window(&mut self, ctx: &egui::Context) {
+        let Some(import) = &self.table_data.csv_import else {
+            return;
+        };
+
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        egui::Window::new("Import CSV")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.label(format!("{} changed fields", import.changes.len()));
+                if !import.unknown_records.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!(
+                            "{} records are not in the plugin and are skipped",
+                            import.unknown_records.len()
+                        ),
+                    )
+                    .on_hover_text(import.unknown_records.join("\n"));
+                }
+                ui.horizontal(|ui| {
+                    apply = ui
+                        .add_enabled(!import.changes.is_empty(), egui::Button::new("Apply"))
+                        .clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+                ui.separator();
+
+                egui::ScrollArea::both().show(ui, |ui| {
+                    egui::Grid::new("csv_import_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for change in import.changes.iter() {
+                                ui.label(&change.record_id);
+                                ui.label(&change.field);
+                                ui.colored_label(egui::Color32::RED, &change.old_value);
+                                ui.colored_label(egui::Color32::GREEN, &change.new_value);
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if apply {
+            if let (Some(import), Some(plugin)) = (
+                self.table_data.csv_import.take(),
+                self.edit_data
+                    .plugins
+                    .iter_mut()
+                    .find(|p| p.id == self.edit_data.current_plugin_id),
+            ) {
+                let errors = apply_csv_changes(plugin, &import.changes);
+                if errors.is_empty() {
+                    self.toasts
+                        .success(format!("Applied {} changes", import.changes.len()));
+                } else {
+                    self.toasts.error(format!(
+                        "{} changes could not be applied:\n{}",
+                        errors.len(),
+                        errors.join("\n")
+                    ));
+                }
+                self.table_data.source = None;
+            }
+        }
+        if !open || cancel {
+            self.table_data.csv_import = None;
+        }
+    }
+
+    /// Parses the cell text and writes it into the edited record, the row is reset on errors
+    fn apply_table_cell(&mut self, row: usize, column: usize, text: &str) {
+        let data = &mut self.table_data;
+        let Some(plugin) = self
+            .edit_data
+            .plugins
+            .iter_mut()
+            .find(|p| p.id == self.edit_data.current_plugin_id)
+        else {
+            return;
+        };
+        let table_row = &mut data.rows[row];
+        if text == format_cell(&table_row.json[column]) {
+            return;
+        }
+        let Some(record) = plugin
+            .edited_records
+            .get(&table_row.id)
+            .or_else(|| plugin.records.get(&table_row.id))
+        else {
+            return;
+        };
+
+        let result = parse_cell(&table_row.json[column], text)
+            .and_then(|value| set_field_value(record, &data.columns[column], value));
+        match result {
+            Ok(edited) => {
+                *table_row =
+                    get_table_row(&table_row.id, &get_record_fields(&edited), &data.columns);
+                plugin.edited_records.insert(table_row.id.clone(), edited);
+                plugin.clear_cache();
+            }
+            Err(error) => {
+                let fields = get_record_fields(record);
+                *table_row = get_table_row(&table_row.id, &fields, &data.columns);
+                self.toasts
+                    .error(format!("{}: {}", data.columns[column], error));
+            }
+        }
+    }
+
+    /// All records of one type of the current plugin as editable table
+    pub fn table_view(&mut self, ui: &mut egui::Ui) {
+        let Some(plugin) = self
+            .edit_data
+            .plugins
+            .iter()
+            .find(|p| p.id == self.edit_data.current_plugin_id)
+        else {
+            ui.label("Please open a plugin first");
+            return;
+        };
+
+        // rebuild the rows when the plugin or the record type changed
+        let data = &mut self.table_data;
+        let source = (plugin.id.clone(), data.record_type);
+        if data.source.as_ref() != Some(&source) {
+            (data.columns, data.rows) = get_table(plugin, &data.record_type.to_string());
+            data.sort_column = None;
+            data.visible = None;
+            data.source = Some(source);
+        }
+        if data.rows.is_empty() {
+            ui.label("No records of this type");
+            return;
+        }
+
+        // filtering and sorting every frame would undo the virtualized rows
+        let visible = data.visible.get_or_insert_with(|| {
+            get_visible_rows(
+                &data.rows,
+                &data.filter,
+                data.sort_column,
+                data.sort_ascending,
+            )
+        });
+
+        let mut edits: Vec<(usize, usize, String)> = vec![];
+        let mut sort = None;
+        let text_height = egui::TextStyle::Body.resolve(ui.style()).size + 6.0;
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            TableBuilder::new(ui)
+                .striped(true)
+                .resizable(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .column(Column::initial(200.0).at_least(80.0).clip(true))
+                .columns(
+                    Column::initial(100.0)
+                        .at_least(40.0)
+                        .resizable(true)
+                        .clip(true),
+                    data.columns.len(),
+                )
+                .header(text_height, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("Record");
+                    });
+                    for (i, column) in data.columns.iter().enumerate() {
+                        header.col(|ui| {
+                            let arrow = match data.sort_column {
+                                Some(c) if c == i && data.sort_ascending => " ⏶",
+                                Some(c) if c == i => " ⏷",
+                                _ => "",
+                            };
+                            if ui
+                                .button(format!("{}{}", column, arrow))
+                                .on_hover_text(column)
+                                .clicked()
+                            {
+                                sort = Some(i);
+                            }
+                        });
+                    }
+                })
+                .body(|body| {
+                    // only the rows in view are laid out
+                    body.rows(text_height, visible.len(), |mut table_row| {
+                        let position = table_row.index();
+                        let index = visible[position];
+                        let row = &mut data.rows[index];
+                        table_row.col(|ui| {
+                            ui.label(&row.id);
+                        });
+                        for column in 0..data.columns.len() {
+                            table_row.col(|ui| {
+                                // the id is the key of the record
+                                if data.columns[column] == "id" {
+                                    ui.label(&row.values[column]);
+                                    return;
+                                }
+
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut row.values[column])
+                                        .desired_width(f32::INFINITY),
+                                );
+                                if response.lost_focus() {
+                                    edits.push((index, column, row.values[column].clone()));
+                                }
+                                response.context_menu(|ui| {
+                                    if ui.button("Fill down").clicked() {
+                                        // this row and all visible rows below get this value
+                                        for &below in visible.iter().skip(position) {
+                                            edits.push((below, column, row.values[column].clone()));
+                                        }
+                                        ui.close_menu();
+                                    }
+                                });
+                            });
+                        }
+                    });
+                });
+        });
+
+        if let Some(column) = sort {
+            let data = &mut self.table_data;
+            if data.sort_column == Some(column) {
+                data.sort_ascending = !data.sort_ascending;
+            } else {
+                data.sort_column = Some(column);
+                data.sort_ascending = true;
+            }
+            data.visible = None;
+        }
+        if !edits.is_empty() {
+            for (row, column, text) in edits {
+                self.apply_table_cell(row, column, &text);
+            }
+            self.table_data.visible = None;
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use egui::{text::LayoutJob, Color32, TextFormat};
+
+use crate::{
+    search::{build_search_regex, search_text, TextMatch},
+    TemplateApp,
+};
+
+/// maximum number of results that are listed
+const MAX_RESULTS: usize = 1000;
+
+/// The matched line with the match highlighted
+fn get_preview(ui: &egui::Ui, result: &TextMatch) -> LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let plain = TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+    let highlighted = TextFormat {
+        background: ui.visuals().selection.bg_fill,
+        ..TextFormat::simple(font_id, ui.visuals().strong_text_color())
+    };
+
+    let text = result.text.trim_end();
+    let range = result.range.start.min(text.len())..result.range.end.min(text.len());
+    let mut job = LayoutJob::default();
+    job.append(text[..range.start].trim_start(), 0.0, plain.clone());
+    job.append(&text[range.clone()], 0.0, highlighted);
+    job.append(&text[range.end..], 0.0, plain);
+    job
+}
+
+impl TemplateApp {
+    /// Full-text search over scripts and dialogue of all open plugins
+    pub fn text_search_window(&mut self, ctx: &egui::Context) {
+        if !self.text_search_data.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut jump = None;
+        egui::Window::new("Text search")
+            .open(&mut open)
+            .default_width(700.0)
+            .show(ctx, |ui| {
+                let data = &mut self.text_search_data;
+                let mut search = false;
+                ui.horizontal(|ui| {
+                    let response = ui.text_edit_singleline(&mut data.query);
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        search = true;
+                    }
+                    search |= ui.button("Search").clicked();
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut data.use_regex, "Regex");
+                    ui.checkbox(&mut data.whole_word, "Whole word");
+                    ui.checkbox(&mut data.case_sensitive, "Case sensitive");
+                });
+
+                if search {
+                    match build_search_regex(
+                        &data.query,
+                        data.use_regex,
+                        data.whole_word,
+                        data.case_sensitive,
+                    ) {
+                        Ok(regex) => {
+                            data.results = search_text(&self.edit_data.plugins, &regex);
+                            data.error = None;
+                        }
+                        Err(error) => {
+                            data.results.clear();
+                            data.error = Some(error.to_string());
+                        }
+                    }
+                }
+                if let Some(error) = &data.error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.separator();
+
+                ui.label(format!("{} matches", data.results.len()));
+                egui::ScrollArea::both().show(ui, |ui| {
+                    egui::Grid::new("text_search_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for result in data.results.iter().take(MAX_RESULTS) {
+                                if ui.button("Open").clicked() {
+                                    jump =
+                                        Some((result.plugin_id.clone(), result.record_id.clone()));
+                                }
+                                ui.label(
+                                    std::path::Path::new(&result.plugin_id)
+                                        .file_name()
+                                        .map(|n| n.to_string_lossy().to_string())
+                                        .unwrap_or_default(),
+                                );
+                                ui.label(&result.record_id);
+                                ui.label(format!("{} {}", result.field, result.line + 1));
+                                ui.label(get_preview(ui, result))
+                                    .on_hover_text_at_pointer(&result.context);
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if !open {
+            self.text_search_data.open = false;
+        }
+        if let Some((plugin_id, record_id)) = jump {
+            self.open_record_in_editor(&plugin_id, &record_id);
+        }
+    }
+}